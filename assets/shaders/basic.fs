@@ -7,42 +7,83 @@ in vec3 vNormal;
 in vec2 vUV;
 in vec3 vTangent;
 in vec3 vBinormal;
+in vec4 vColor;
 
+#ifdef HAS_ALBEDO_MAP
 uniform sampler2D uAlbedoTexture;
+#endif
+#ifdef HAS_NORMAL_MAP
 uniform sampler2D uNormalTexture;
+#endif
+#ifdef HAS_ROUGHNESS_MAP
 uniform sampler2D uRoughnessTexture;
+#endif
+#ifdef HAS_METALLIC_MAP
 uniform sampler2D uMetallicTexture;
+#endif
+#ifdef HAS_METALLIC_ROUGHNESS_MAP
+uniform sampler2D uMetallicRoughnessTexture;
+#endif
+#ifdef HAS_OCCLUSION_MAP
+uniform sampler2D uOcclusionTexture;
+#endif
+#ifdef HAS_EMISSIVE_MAP
+uniform sampler2D uEmissiveTexture;
+#endif
 
-uniform float uTime;
+#include "common/uniforms.glsl"
+#include "common/lighting.glsl"
 
 void main(){
+#ifdef HAS_ALPHA_MASK
     float mask = textureLod(uAlbedoTexture, vUV, 0.0).a;
     if(mask < 0.5){
         discard;
     }
+#endif
 
+#ifdef HAS_ALBEDO_MAP
     vec3 albedo = pow(texture(uAlbedoTexture, vUV).rgb, vec3(2.2));
-    vec3 normal = texture(uNormalTexture, vUV).xyz * 2.0 - 1.0;
+#else
+    vec3 albedo = vec3(0.8);
+#endif
+    albedo *= vColor.rgb;
+
+#if defined(HAS_METALLIC_ROUGHNESS_MAP)
+    float roughness = texture(uMetallicRoughnessTexture, vUV).g;
+#elif defined(HAS_ROUGHNESS_MAP)
     float roughness = texture(uRoughnessTexture, vUV).r;
+#else
+    float roughness = 0.5;
+#endif
+
+#if defined(HAS_METALLIC_ROUGHNESS_MAP)
+    float metallic = texture(uMetallicRoughnessTexture, vUV).b;
+#elif defined(HAS_METALLIC_MAP)
     float metallic = texture(uMetallicTexture, vUV).r;
+#else
+    float metallic = 0.0;
+#endif
 
+#ifdef HAS_NORMAL_MAP
+    vec3 normal = texture(uNormalTexture, vUV).xyz * 2.0 - 1.0;
     mat3 tbn = mat3(normalize(vTangent), normalize(vBinormal), normalize(vNormal));
     vec3 n = normalize(tbn * normal);
+#else
+    vec3 n = normalize(vNormal);
+#endif
 
-    vec3 col = vec3(0.0);
+    vec3 col = computeLighting(n);
 
-    vec3 sunDirection = normalize(vec3(0.6, 0.35, 0.5));
-    float sunDiffuse = clamp(dot(n, sunDirection), 0.0, 1.0);
-
-    float skyDiffuse = sqrt(clamp(0.5+0.5*dot(n, vec3(0.0, 1.0, 0.0)), 0.0, 1.0));
-
-    float bounceDiffuse = clamp(dot(n, vec3(0.0, -1.0, 0.0)), 0.0, 1.0);
+    col *= albedo;
 
-    col += sunDiffuse * vec3(8.10, 6.00, 4.20) * 0.4;
-    col += skyDiffuse * vec3(0.50, 0.70, 1.00);
-    col += bounceDiffuse * vec3(0.20, 0.70, 0.10) * 0.25;
+#ifdef HAS_OCCLUSION_MAP
+    col *= texture(uOcclusionTexture, vUV).r;
+#endif
 
-    col *= albedo;
+#ifdef HAS_EMISSIVE_MAP
+    col += pow(texture(uEmissiveTexture, vUV).rgb, vec3(2.2));
+#endif
 
     FragColor = vec4(pow(col, vec3(0.4545)), 1.0);
 }
\ No newline at end of file
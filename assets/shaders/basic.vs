@@ -4,16 +4,16 @@ layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec3 aNormal;
 layout (location = 2) in vec2 aUV;
 layout (location = 3) in vec4 aTangent;
+layout (location = 4) in vec4 aColor;
 
 out vec3 vPos;
 out vec3 vNormal;
 out vec2 vUV;
 out vec3 vTangent;
 out vec3 vBinormal;
+out vec4 vColor;
 
-uniform mat4 uModelMatrix;
-uniform mat4 uNormalMatrix;
-uniform mat4 uModelViewProjectionMatrix;
+#include "common/uniforms.glsl"
 
 void main(){
     vec3 p = aPos;
@@ -22,6 +22,7 @@ void main(){
     vPos = normalize(uModelMatrix * vec4(p, 1.0)).xyz;
 
     vUV = aUV;
+    vColor = aColor;
 
     vNormal = normalize((uNormalMatrix * vec4(aNormal, 0.0)).xyz);
     vTangent = normalize((uNormalMatrix * vec4(aTangent.xyz, 0.0)).xyz);
@@ -0,0 +1,16 @@
+vec3 computeLighting(vec3 n){
+    vec3 col = vec3(0.0);
+
+    vec3 sunDirection = normalize(vec3(0.6, 0.35, 0.5));
+    float sunDiffuse = clamp(dot(n, sunDirection), 0.0, 1.0);
+
+    float skyDiffuse = sqrt(clamp(0.5+0.5*dot(n, vec3(0.0, 1.0, 0.0)), 0.0, 1.0));
+
+    float bounceDiffuse = clamp(dot(n, vec3(0.0, -1.0, 0.0)), 0.0, 1.0);
+
+    col += sunDiffuse * vec3(8.10, 6.00, 4.20) * 0.4;
+    col += skyDiffuse * vec3(0.50, 0.70, 1.00);
+    col += bounceDiffuse * vec3(0.20, 0.70, 0.10) * 0.25;
+
+    return col;
+}
@@ -0,0 +1,14 @@
+layout (std140, binding = 0) uniform PerFrame {
+    mat4 uViewMatrix;
+    mat4 uProjectionMatrix;
+    mat4 uViewProjectionMatrix;
+    vec3 uCameraPosition;
+    float uTime;
+    vec2 uResolution;
+};
+
+layout (std140, binding = 1) uniform PerObject {
+    mat4 uModelMatrix;
+    mat4 uNormalMatrix;
+    mat4 uModelViewProjectionMatrix;
+};
@@ -0,0 +1,42 @@
+#version 460 core
+
+// Draws the normal (blue), tangent (red) and binormal (green) of every vertex
+// as short lines in world space.
+
+layout (triangles) in;
+layout (line_strip, max_vertices = 18) out;
+
+in VertexData {
+    vec3 normal;
+    vec4 tangent;
+} gIn[];
+
+out vec3 gColor;
+
+#include "../common/uniforms.glsl"
+
+#ifndef LINE_LENGTH
+#define LINE_LENGTH 0.05
+#endif
+
+void emitLine(vec3 origin, vec3 direction, vec3 color){
+    gColor = color;
+    gl_Position = uViewProjectionMatrix * vec4(origin, 1.0);
+    EmitVertex();
+    gl_Position = uViewProjectionMatrix * vec4(origin + direction * LINE_LENGTH, 1.0);
+    EmitVertex();
+    EndPrimitive();
+}
+
+void main(){
+    for(int i = 0; i < 3; i++){
+        vec3 p = gl_in[i].gl_Position.xyz;
+        vec3 n = gIn[i].normal;
+        vec3 t = gIn[i].tangent.xyz;
+        vec3 b = normalize(cross(n, t)) * gIn[i].tangent.w;
+
+        emitLine(p, n, vec3(0.0, 0.0, 1.0));
+        emitLine(p, t, vec3(1.0, 0.0, 0.0));
+        emitLine(p, b, vec3(0.0, 1.0, 0.0));
+    }
+}
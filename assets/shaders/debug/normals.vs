@@ -0,0 +1,19 @@
+#version 460 core
+
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 3) in vec4 aTangent;
+
+out VertexData {
+    vec3 normal;
+    vec4 tangent;
+} vOut;
+
+#include "../common/uniforms.glsl"
+
+void main(){
+    gl_Position = uModelMatrix * vec4(aPos, 1.0);
+
+    vOut.normal = normalize((uNormalMatrix * vec4(aNormal, 0.0)).xyz);
+    vOut.tangent = vec4(normalize((uNormalMatrix * vec4(aTangent.xyz, 0.0)).xyz), aTangent.w);
+}
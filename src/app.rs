@@ -0,0 +1,135 @@
+use glfw::{Action, Key};
+
+use tofu::cgmath::*;
+
+use tofu::{
+    turntable_matrix, BoundingSphere, Camera, Model, ModelLodState, Renderer, Result,
+    ShaderBuilder, ShaderPermutations, Window,
+};
+
+const SCREEN_WIDTH: u32 = 1600;
+const SCREEN_HEIGHT: u32 = 900;
+const FOV: f32 = 50.0;
+
+pub struct App {
+    camera: Camera,
+    model_filepath: String,
+    show_normals: bool,
+}
+
+impl App {
+    pub fn new(model_filepath: &str) -> App {
+        App {
+            camera: Camera::new(),
+            model_filepath: model_filepath.into(),
+            show_normals: false,
+        }
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        let mut window = Window::new(SCREEN_WIDTH, SCREEN_HEIGHT, "Tofu")?;
+
+        let mut permutations = ShaderPermutations::new(
+            ShaderBuilder::new()
+                .vertex("assets/shaders/basic.vs")
+                .fragment("assets/shaders/basic.fs"),
+        );
+
+        let mut normals_shader = ShaderBuilder::new()
+            .vertex("assets/shaders/debug/normals.vs")
+            .geometry("assets/shaders/debug/normals.gs")
+            .fragment("assets/shaders/debug/normals.fs")
+            .build()?;
+
+        let model = Model::new(&self.model_filepath)?;
+        for warning in &model.warnings {
+            log::warn!("{}", warning);
+        }
+
+        let mut model_lod = ModelLodState::default();
+        let mut renderer = Renderer::new();
+
+        self.camera
+            .make_perspective(FOV, SCREEN_WIDTH as f32 / SCREEN_HEIGHT as f32);
+        self.camera.frame(&turntable_sphere(&model.bounds().sphere));
+
+        let mut last_frame = window.get_time();
+        let mut delta_time;
+
+        while !window.should_close() {
+            self.process_events(&mut window, &mut renderer);
+            self.process_input(&mut window);
+
+            let time = window.get_time();
+            delta_time = time - last_frame;
+            last_frame = time;
+
+            self.camera.update(delta_time);
+
+            report_reload(permutations.reload_if_changed());
+            report_reload(normals_shader.reload_if_changed());
+
+            renderer.clear();
+            renderer.begin_frame(&self.camera, time);
+            let model_matrix = turntable_matrix(time);
+            renderer.draw_model_permutations(
+                &mut permutations,
+                &model,
+                &model_matrix,
+                &mut model_lod,
+            )?;
+            if self.show_normals {
+                renderer.draw_model(&normals_shader, &model, &model_matrix, &mut model_lod);
+            }
+
+            window.swap_buffers();
+        }
+
+        Ok(())
+    }
+
+    fn process_events(&mut self, window: &mut Window, renderer: &mut Renderer) {
+        for event in window.poll_events() {
+            match event {
+                glfw::WindowEvent::FramebufferSize(width, height) if width != 0 && height != 0 => {
+                    renderer.set_viewport(width, height);
+                    self.camera
+                        .make_perspective(FOV, width as f32 / height as f32);
+                }
+                glfw::WindowEvent::Key(Key::N, _, Action::Press, _) => {
+                    self.show_normals = !self.show_normals;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn process_input(&mut self, window: &mut Window) {
+        if window.handle().get_key(Key::Escape) == Action::Press {
+            window.set_should_close(true)
+        }
+
+        self.camera.process_input(window.handle());
+    }
+}
+
+/// Logs the outcome of a hot reload. Failed reloads keep the previous
+/// program, so the viewer carries on.
+fn report_reload(result: Result<bool>) {
+    match result {
+        Ok(true) => log::info!("Reloaded shaders"),
+        Ok(false) => {}
+        Err(error) => log::error!("Failed to reload shaders: {}", error),
+    }
+}
+
+/// A sphere containing `sphere` at every angle of `turntable_matrix`, which
+/// spins models around the Y axis.
+fn turntable_sphere(sphere: &BoundingSphere) -> BoundingSphere {
+    let axis_distance = vec2(sphere.center.x, sphere.center.z).magnitude();
+
+    BoundingSphere {
+        center: Point3::new(0.0, sphere.center.y, 0.0),
+        radius: sphere.radius + axis_distance,
+    }
+}
@@ -0,0 +1,162 @@
+use cgmath::prelude::*;
+use cgmath::{vec3, Matrix4, Point3, Vector3};
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+/// A sphere enclosing some geometry, not necessarily the smallest one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+/// A box and a sphere around the same geometry. The sphere is centered on the
+/// box, which keeps it close to the smallest one for most meshes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub aabb: Aabb,
+    pub sphere: BoundingSphere,
+}
+
+impl Aabb {
+    /// The smallest box around `points`, or `None` if there are none.
+    pub fn from_points<I>(points: I) -> Option<Aabb>
+    where
+        I: IntoIterator<Item = Point3<f32>>,
+    {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(Aabb::point(first), |aabb, p| aabb.union(&Aabb::point(p))))
+    }
+
+    /// An empty box at `point`.
+    pub fn point(point: Point3<f32>) -> Aabb {
+        Aabb {
+            min: point,
+            max: point,
+        }
+    }
+
+    pub fn center(&self) -> Point3<f32> {
+        self.min.midpoint(self.max)
+    }
+
+    /// Half the box's size along each axis.
+    pub fn extents(&self) -> Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Point3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Point3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    /// The box around this one once transformed. It contains everything this
+    /// one did, but may be larger than the box around the transformed contents.
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> Aabb {
+        let center = transform.transform_point(self.center());
+        let e = self.extents();
+        // Every axis of the result spans the absolute projections of the
+        // transformed extents onto it.
+        let extents = vec3(
+            transform.x.x.abs() * e.x + transform.y.x.abs() * e.y + transform.z.x.abs() * e.z,
+            transform.x.y.abs() * e.x + transform.y.y.abs() * e.y + transform.z.y.abs() * e.z,
+            transform.x.z.abs() * e.x + transform.y.z.abs() * e.y + transform.z.z.abs() * e.z,
+        );
+
+        Aabb {
+            min: center - extents,
+            max: center + extents,
+        }
+    }
+}
+
+impl BoundingSphere {
+    /// The sphere around this one once transformed, scaled by the transform's
+    /// largest scale factor.
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> BoundingSphere {
+        BoundingSphere {
+            center: transform.transform_point(self.center),
+            radius: self.radius * max_scale(transform),
+        }
+    }
+}
+
+impl Bounds {
+    /// Bounds around `points`, or `None` if there are none.
+    pub fn from_points<I>(points: I) -> Option<Bounds>
+    where
+        I: IntoIterator<Item = Point3<f32>>,
+        I::IntoIter: Clone,
+    {
+        let points = points.into_iter();
+        let aabb = Aabb::from_points(points.clone())?;
+        let center = aabb.center();
+        let radius = points.map(|p| (p - center).magnitude()).fold(0.0, f32::max);
+
+        Some(Bounds {
+            aabb,
+            sphere: BoundingSphere { center, radius },
+        })
+    }
+
+    /// Bounds around all of `bounds`, or `None` if there are none. The sphere
+    /// is centered on the combined box, like the ones `from_points` builds.
+    pub fn merge(bounds: &[Bounds]) -> Option<Bounds> {
+        let aabb = bounds.iter().map(|b| b.aabb).reduce(|a, b| a.union(&b))?;
+        let center = aabb.center();
+        let radius = bounds
+            .iter()
+            .map(|b| (b.sphere.center - center).magnitude() + b.sphere.radius)
+            .fold(0.0, f32::max);
+
+        Some(Bounds {
+            aabb,
+            sphere: BoundingSphere { center, radius },
+        })
+    }
+
+    /// Empty bounds at `point`.
+    pub fn point(point: Point3<f32>) -> Bounds {
+        Bounds {
+            aabb: Aabb::point(point),
+            sphere: BoundingSphere {
+                center: point,
+                radius: 0.0,
+            },
+        }
+    }
+
+    pub fn transformed(&self, transform: &Matrix4<f32>) -> Bounds {
+        Bounds {
+            aabb: self.aabb.transformed(transform),
+            sphere: self.sphere.transformed(transform),
+        }
+    }
+}
+
+/// Largest factor by which `transform` scales lengths.
+pub(crate) fn max_scale(transform: &Matrix4<f32>) -> f32 {
+    transform
+        .x
+        .truncate()
+        .magnitude()
+        .max(transform.y.truncate().magnitude())
+        .max(transform.z.truncate().magnitude())
+}
@@ -3,6 +3,8 @@ use cgmath::{Matrix4, Vector3};
 
 use glfw::{Action, CursorMode, Key, MouseButton};
 
+use crate::{BoundingSphere, Frustum};
+
 const Z_NEAR: f32 = 0.01;
 const Z_FAR: f32 = 100.0;
 const MOVEMENT_SPEED: f32 = 10.0;
@@ -12,6 +14,11 @@ const LOOK_BUTTON: MouseButton = MouseButton::Button2;
 pub struct Camera {
     position: Point3<f32>,
     projection: Matrix4<f32>,
+    /// Vertical field of view in degrees and aspect ratio, once
+    /// `make_perspective` was called.
+    perspective: Option<(f32, f32)>,
+    z_near: f32,
+    z_far: f32,
     view: Matrix4<f32>,
     view_projection: Matrix4<f32>,
     velocity: Vector3<f32>,
@@ -27,11 +34,20 @@ pub struct Camera {
     mouse_lock_pos: Vector2<f32>,
 }
 
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera::new()
+    }
+}
+
 impl Camera {
     pub fn new() -> Camera {
         Camera {
             position: Point3::new(0.0, 0.0, 0.0),
             projection: Transform::one(),
+            perspective: None,
+            z_near: Z_NEAR,
+            z_far: Z_FAR,
             view: Transform::one(),
             view_projection: Transform::one(),
             velocity: Vector3::zero(),
@@ -63,6 +79,50 @@ impl Camera {
                 + current_velocity.z * self.forward,
         );
 
+        self.update_view();
+    }
+
+    /// Turns the camera to face `target` immediately, without easing. Keeps
+    /// the current orientation if `target` is the camera's position.
+    pub fn look_at(&mut self, target: Point3<f32>) {
+        let offset = target - self.position;
+        let distance = offset.magnitude();
+        if distance == 0.0 || !distance.is_finite() {
+            return;
+        }
+        let direction = offset / distance;
+
+        self.pitch = direction.y.asin().to_degrees().clamp(-89.99, 89.99);
+        self.yaw = direction.z.atan2(direction.x).to_degrees();
+        self.target_pitch = self.pitch;
+        self.target_yaw = self.yaw;
+
+        self.update_direction_vectors();
+        self.update_view();
+    }
+
+    /// Moves the camera in front of `sphere`, looking down -Z, close enough
+    /// for it to fill the narrower of the projection's fields of view. The far
+    /// plane is pushed back for spheres that would not fit in front of it.
+    pub fn frame(&mut self, sphere: &BoundingSphere) {
+        // The projection's diagonal holds the cotangents of the half fields
+        // of view; the distance at which a sphere touches the sides of a view
+        // cone is its radius over the sine of the cone's half angle.
+        let cotangent = self.projection.x.x.max(self.projection.y.y);
+        let distance = sphere.radius.max(Z_NEAR) * (1.0 + cotangent * cotangent).sqrt();
+
+        // Leaves room to back away, and keeps the near plane at the same
+        // fraction of the far one so depth precision does not change.
+        self.z_far = Z_FAR.max((distance + sphere.radius) * 2.0);
+        self.z_near = self.z_far * (Z_NEAR / Z_FAR);
+        self.update_projection();
+
+        self.position = sphere.center + vec3(0.0, 0.0, distance);
+        self.velocity = Vector3::zero();
+        self.look_at(sphere.center);
+    }
+
+    fn update_view(&mut self) {
         self.view = Matrix4::look_at(self.position, self.position + self.forward, self.up);
         self.view_projection = self.projection * self.view;
     }
@@ -97,7 +157,7 @@ impl Camera {
 
         self.target_yaw += cursor_delta.x * MOUSE_SENSITIVITY;
         self.target_pitch += -cursor_delta.y * MOUSE_SENSITIVITY;
-        self.target_pitch = self.target_pitch.min(89.99).max(-89.99);
+        self.target_pitch = self.target_pitch.clamp(-89.99, 89.99);
 
         self.update_direction_vectors();
 
@@ -149,11 +209,20 @@ impl Camera {
     }
 
     pub fn make_perspective(&mut self, fovy: f32, aspect_ratio: f32) {
-        self.projection = perspective(Deg(fovy), aspect_ratio, Z_NEAR, Z_FAR);
+        self.perspective = Some((fovy, aspect_ratio));
+        self.update_projection();
+    }
+
+    fn update_projection(&mut self) {
+        if let Some((fovy, aspect_ratio)) = self.perspective {
+            self.projection = perspective(Deg(fovy), aspect_ratio, self.z_near, self.z_far);
+            self.view_projection = self.projection * self.view;
+        }
     }
 
     pub fn set_position(&mut self, new_position: Point3<f32>) {
         self.position = new_position;
+        self.update_view();
     }
     pub fn translate(&mut self, translation: Vector3<f32>) {
         self.position += translation;
@@ -162,4 +231,21 @@ impl Camera {
     pub fn get_view_projection(&self) -> &Matrix4<f32> {
         &self.view_projection
     }
+
+    /// The world space planes of what the camera sees.
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.view_projection)
+    }
+
+    pub fn get_view(&self) -> &Matrix4<f32> {
+        &self.view
+    }
+
+    pub fn get_projection(&self) -> &Matrix4<f32> {
+        &self.projection
+    }
+
+    pub fn get_position(&self) -> Point3<f32> {
+        self.position
+    }
 }
@@ -0,0 +1,173 @@
+use gl::types::*;
+
+use std::ops::{BitOr, Deref, DerefMut};
+use std::path::PathBuf;
+
+use crate::{
+    ProgramCache, Result, Shader, ShaderPreprocessor, ShaderStage, Std430, StorageBuffer, Texture,
+};
+
+/// A program made of a single compute stage.
+///
+/// Dereferences to `Shader` for uniform setters, reflection and hot reloading.
+pub struct ComputeShader {
+    shader: Shader,
+}
+
+/// Memory barrier bits for `ComputeShader::memory_barrier`, combinable with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBarrier(GLbitfield);
+
+impl MemoryBarrier {
+    pub const VERTEX_ATTRIB_ARRAY: MemoryBarrier =
+        MemoryBarrier(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+    pub const ELEMENT_ARRAY: MemoryBarrier = MemoryBarrier(gl::ELEMENT_ARRAY_BARRIER_BIT);
+    pub const UNIFORM: MemoryBarrier = MemoryBarrier(gl::UNIFORM_BARRIER_BIT);
+    pub const TEXTURE_FETCH: MemoryBarrier = MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT);
+    pub const SHADER_IMAGE_ACCESS: MemoryBarrier =
+        MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+    pub const COMMAND: MemoryBarrier = MemoryBarrier(gl::COMMAND_BARRIER_BIT);
+    pub const BUFFER_UPDATE: MemoryBarrier = MemoryBarrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+    pub const TEXTURE_UPDATE: MemoryBarrier = MemoryBarrier(gl::TEXTURE_UPDATE_BARRIER_BIT);
+    pub const SHADER_STORAGE: MemoryBarrier = MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+    pub const ALL: MemoryBarrier = MemoryBarrier(gl::ALL_BARRIER_BITS);
+}
+
+impl BitOr for MemoryBarrier {
+    type Output = MemoryBarrier;
+
+    fn bitor(self, other: MemoryBarrier) -> MemoryBarrier {
+        MemoryBarrier(self.0 | other.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageAccess {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+impl ImageAccess {
+    fn gl_access(self) -> GLenum {
+        match self {
+            ImageAccess::ReadOnly => gl::READ_ONLY,
+            ImageAccess::WriteOnly => gl::WRITE_ONLY,
+            ImageAccess::ReadWrite => gl::READ_WRITE,
+        }
+    }
+}
+
+impl ComputeShader {
+    pub fn new(compute_filepath: &str) -> Result<ComputeShader> {
+        ComputeShader::with_defines(compute_filepath, &[])
+    }
+
+    pub fn with_defines(compute_filepath: &str, defines: &[(&str, &str)]) -> Result<ComputeShader> {
+        let mut preprocessor = ShaderPreprocessor::new();
+        for (name, value) in defines {
+            preprocessor.define(name, value);
+        }
+
+        let shader = Shader::from_sources(
+            vec![(ShaderStage::Compute, PathBuf::from(compute_filepath))],
+            preprocessor,
+            Some(ProgramCache::default()),
+        )?;
+
+        Ok(ComputeShader { shader })
+    }
+
+    /// The `local_size_x/y/z` declared in the shader.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the program was linked on.
+    pub unsafe fn work_group_size(&self) -> [u32; 3] {
+        let mut size = [0; 3];
+        gl::GetProgramiv(
+            self.shader.id(),
+            gl::COMPUTE_WORK_GROUP_SIZE,
+            size.as_mut_ptr(),
+        );
+        [size[0] as u32, size[1] as u32, size[2] as u32]
+    }
+
+    /// Runs `x * y * z` work groups.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the program was linked on.
+    pub unsafe fn dispatch(&self, x: u32, y: u32, z: u32) {
+        self.shader.use_program();
+        gl::DispatchCompute(x, y, z);
+    }
+
+    /// Runs enough work groups to cover `width * height * depth` invocations.
+    /// The shader has to discard invocations outside of that range itself.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the program was linked on.
+    pub unsafe fn dispatch_invocations(&self, width: u32, height: u32, depth: u32) {
+        // Linked compute programs never report a zero size, but a failed
+        // query leaves it at zero.
+        let [x, y, z] = self.work_group_size().map(|size| size.max(1));
+        self.dispatch(width.div_ceil(x), height.div_ceil(y), depth.div_ceil(z));
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the buffer was created on.
+    pub unsafe fn bind_storage_buffer<T: Std430>(&self, binding: u32, buffer: &StorageBuffer<T>) {
+        buffer.bind(binding);
+    }
+
+    /// Binds mip `level` of `texture` to image `unit`. `format` is the sized
+    /// internal format the shader's `layout(...)` qualifier expects, e.g. `gl::RGBA8`.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the texture was created on.
+    pub unsafe fn bind_image(
+        &self,
+        unit: u32,
+        texture: &Texture,
+        level: i32,
+        access: ImageAccess,
+        format: GLenum,
+    ) {
+        gl::BindImageTexture(
+            unit,
+            texture.id(),
+            level,
+            gl::FALSE,
+            0,
+            access.gl_access(),
+            format,
+        );
+    }
+
+    /// Makes writes from previous dispatches visible to the accesses in `barrier`.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context.
+    pub unsafe fn memory_barrier(&self, barrier: MemoryBarrier) {
+        gl::MemoryBarrier(barrier.0);
+    }
+}
+
+impl Deref for ComputeShader {
+    type Target = Shader;
+
+    fn deref(&self) -> &Shader {
+        &self.shader
+    }
+}
+
+impl DerefMut for ComputeShader {
+    fn deref_mut(&mut self) -> &mut Shader {
+        &mut self.shader
+    }
+}
@@ -0,0 +1,123 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::ShaderStage;
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    ImageDecode {
+        path: PathBuf,
+        reason: String,
+    },
+    ImageEncode {
+        path: PathBuf,
+        reason: String,
+    },
+    ObjParse {
+        path: PathBuf,
+        source: tobj::LoadError,
+    },
+    MeshParse {
+        path: PathBuf,
+        reason: String,
+    },
+    GltfParse {
+        path: PathBuf,
+        source: gltf::Error,
+    },
+    ShaderInclude {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+    ShaderCompile {
+        stage: ShaderStage,
+        log: String,
+    },
+    ShaderLink {
+        log: String,
+    },
+    Context {
+        reason: String,
+    },
+    FramebufferIncomplete {
+        status: u32,
+    },
+    InvalidPixels {
+        reason: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: io::Error) -> Error {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Attributes invalid pixel data to the image file it was decoded from.
+    pub(crate) fn in_image(self, path: impl Into<PathBuf>) -> Error {
+        match self {
+            Error::InvalidPixels { reason } => Error::ImageDecode {
+                path: path.into(),
+                reason,
+            },
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::ImageDecode { path, reason } => {
+                write!(f, "{}: failed to decode image: {}", path.display(), reason)
+            }
+            Error::ImageEncode { path, reason } => {
+                write!(f, "{}: failed to encode image: {}", path.display(), reason)
+            }
+            Error::ObjParse { path, source } => {
+                write!(f, "{}: failed to parse OBJ: {}", path.display(), source)
+            }
+            Error::MeshParse { path, reason } => {
+                write!(f, "{}: failed to parse mesh: {}", path.display(), reason)
+            }
+            Error::GltfParse { path, source } => {
+                write!(f, "{}: failed to parse glTF: {}", path.display(), source)
+            }
+            Error::ShaderInclude { path, line, reason } => {
+                write!(f, "{}:{}: {}", path.display(), line, reason)
+            }
+            Error::ShaderCompile { stage, log } => {
+                write!(f, "failed to compile {} shader:\n{}", stage, log)
+            }
+            Error::ShaderLink { log } => write!(f, "failed to link shader program:\n{}", log),
+            Error::Context { reason } => write!(f, "failed to create OpenGL context: {}", reason),
+            Error::FramebufferIncomplete { status } => {
+                write!(f, "framebuffer is incomplete (status 0x{:x})", status)
+            }
+            Error::InvalidPixels { reason } => write!(f, "invalid pixel data: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::ObjParse { source, .. } => Some(source),
+            Error::GltfParse { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
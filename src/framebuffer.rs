@@ -0,0 +1,136 @@
+use gl::types::*;
+
+use std::ffi::c_void;
+
+use crate::{Error, Result, RgbaImage};
+
+pub struct Framebuffer {
+    fbo: GLuint,
+    color: GLuint,
+    depth: GLuint,
+    width: u32,
+    height: u32,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Result<Framebuffer> {
+        let mut framebuffer = Framebuffer {
+            fbo: 0,
+            color: 0,
+            depth: 0,
+            width,
+            height,
+        };
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut framebuffer.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.fbo);
+
+            gl::GenRenderbuffers(1, &mut framebuffer.color);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, framebuffer.color);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::RGBA8,
+                width as GLsizei,
+                height as GLsizei,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                framebuffer.color,
+            );
+
+            gl::GenRenderbuffers(1, &mut framebuffer.depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, framebuffer.depth);
+            gl::RenderbufferStorage(
+                gl::RENDERBUFFER,
+                gl::DEPTH24_STENCIL8,
+                width as GLsizei,
+                height as GLsizei,
+            );
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_STENCIL_ATTACHMENT,
+                gl::RENDERBUFFER,
+                framebuffer.depth,
+            );
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(Error::FramebufferIncomplete { status });
+            }
+        }
+
+        Ok(framebuffer)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the framebuffer was created on.
+    pub unsafe fn bind(&self) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the framebuffer was created on.
+    pub unsafe fn unbind(&self) {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+    }
+
+    /// Reads back the color attachment, flipped so the first row is the top of the image.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the framebuffer was created on.
+    pub unsafe fn read_pixels(&self) -> RgbaImage {
+        let row_size = self.width as usize * 4;
+        let mut pixels = vec![0u8; row_size * self.height as usize];
+
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+        gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            self.width as GLsizei,
+            self.height as GLsizei,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as *mut c_void,
+        );
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+
+        let flipped = pixels
+            .chunks_exact(row_size)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+
+        RgbaImage::new(self.width, self.height, flipped)
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.depth);
+            gl::DeleteRenderbuffers(1, &self.color);
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
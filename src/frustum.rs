@@ -0,0 +1,86 @@
+use cgmath::prelude::*;
+use cgmath::{vec3, Matrix4, Point3, Vector3, Vector4};
+
+use crate::{Aabb, BoundingSphere, Bounds};
+
+/// A plane through the points `p` with `normal.dot(p) + distance == 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Signed distance from the plane, positive on the side `normal` points to.
+    pub fn signed_distance(&self, point: Point3<f32>) -> f32 {
+        self.normal.dot(point.to_vec()) + self.distance
+    }
+}
+
+/// The volume a camera sees, bounded by six planes facing inwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far, in that order.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes of the clip volume of `view_projection`, in the
+    /// space its input is in: world space for a camera's view-projection.
+    pub fn from_matrix(view_projection: &Matrix4<f32>) -> Frustum {
+        let m = view_projection;
+        let row = |i: usize| Vector4::new(m.x[i], m.y[i], m.z[i], m.w[i]);
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+        // A clip space point is inside when -w <= x <= w and so on, so each
+        // plane is the last row plus or minus one of the others.
+        let plane = |v: Vector4<f32>| {
+            let normal = vec3(v.x, v.y, v.z);
+            let length = normal.magnitude();
+            Plane {
+                normal: normal / length,
+                distance: v.w / length,
+            }
+        };
+
+        Frustum {
+            planes: [
+                plane(w + x),
+                plane(w - x),
+                plane(w + y),
+                plane(w - y),
+                plane(w + z),
+                plane(w - z),
+            ],
+        }
+    }
+
+    /// Whether any part of the sphere may be inside.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+    }
+
+    /// Whether any part of the box may be inside. Boxes near the frustum's
+    /// corners can pass without being inside.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let furthest = |n: f32, min: f32, max: f32| if n >= 0.0 { max } else { min };
+
+        self.planes.iter().all(|plane| {
+            // The corner furthest along the plane's normal.
+            let corner = Point3::new(
+                furthest(plane.normal.x, aabb.min.x, aabb.max.x),
+                furthest(plane.normal.y, aabb.min.y, aabb.max.y),
+                furthest(plane.normal.z, aabb.min.z, aabb.max.z),
+            );
+            plane.signed_distance(corner) >= 0.0
+        })
+    }
+
+    /// Whether any part of the bounds may be inside, testing the cheaper
+    /// sphere first.
+    pub fn intersects(&self, bounds: &Bounds) -> bool {
+        self.intersects_sphere(&bounds.sphere) && self.intersects_aabb(&bounds.aabb)
+    }
+}
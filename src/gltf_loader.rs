@@ -0,0 +1,249 @@
+use cgmath::*;
+
+use std::path::Path;
+
+use crate::mesh::{compute_tangents, generate_normals, Mesh, TextureData, Vertex};
+use crate::{Error, LoadOptions, MaterialFeatures, Model, ModelNode, Result, Texture};
+
+/// Adds the meshes and nodes of a `.gltf` or `.glb` file to `model`, keeping
+/// the node hierarchy of the default scene (or the first scene if none is
+/// marked as default). Returns the scene's root nodes.
+///
+/// Every triangle primitive becomes one `Mesh`, others are recorded in
+/// `Model::warnings`. Provided tangents are used as-is, missing normals and
+/// tangents are generated. As the glTF spec requires, provided tangents are
+/// ignored for primitives whose normals are generated.
+///
+/// Primitives whose indices reference missing vertices fail with
+/// `Error::MeshParse`.
+pub(crate) fn load_gltf(
+    model: &mut Model,
+    filepath: &Path,
+    options: &LoadOptions,
+) -> Result<Vec<usize>> {
+    let (document, buffers, images) = gltf::import(filepath).map_err(|source| match source {
+        gltf::Error::Io(source) => Error::io(filepath, source),
+        source => Error::GltfParse {
+            path: filepath.into(),
+            source,
+        },
+    })?;
+
+    let key_prefix = filepath.to_string_lossy();
+
+    // Meshes created for each glTF mesh, indexed like `document.meshes()`.
+    let mut mesh_primitives: Vec<Vec<usize>> = Vec::new();
+
+    for gltf_mesh in document.meshes() {
+        let mut primitives = Vec::new();
+
+        for primitive in gltf_mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                model.warnings.push(format!(
+                    "{}: skipped {:?} primitive in mesh {}, only triangles are supported",
+                    filepath.display(),
+                    primitive.mode(),
+                    gltf_mesh.index()
+                ));
+                continue;
+            }
+
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+            let mut vertices: Vec<Vertex> = match reader.read_positions() {
+                Some(positions) => positions
+                    .map(|p| Vertex {
+                        position: Vector3::from(p),
+                        ..Vertex::default()
+                    })
+                    .collect(),
+                None => continue,
+            };
+
+            let mut indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+
+            if vertices.is_empty() || indices.is_empty() {
+                continue;
+            }
+            if let Some(&index) = indices.iter().find(|&&i| i as usize >= vertices.len()) {
+                return Err(Error::MeshParse {
+                    path: filepath.into(),
+                    reason: format!(
+                        "primitive {} of mesh {} references vertex {} but there are only {} vertices",
+                        primitive.index(),
+                        gltf_mesh.index(),
+                        index,
+                        vertices.len()
+                    ),
+                });
+            }
+            if !indices.len().is_multiple_of(3) {
+                return Err(Error::MeshParse {
+                    path: filepath.into(),
+                    reason: format!(
+                        "primitive {} of mesh {} has {} indices, which is not a multiple of 3",
+                        primitive.index(),
+                        gltf_mesh.index(),
+                        indices.len()
+                    ),
+                });
+            }
+
+            if let Some(uvs) = reader.read_tex_coords(0) {
+                for (v, uv) in vertices.iter_mut().zip(uvs.into_f32()) {
+                    v.uv = Vector2::from(uv);
+                }
+            }
+
+            let tangents = match reader.read_normals() {
+                Some(normals) => {
+                    for (v, normal) in vertices.iter_mut().zip(normals) {
+                        v.normal = Vector3::from(normal);
+                    }
+                    reader.read_tangents()
+                }
+                None => {
+                    // Generating normals may split and reorder vertices, and
+                    // the provided tangents were made for other normals.
+                    let (normal_vertices, normal_indices) =
+                        generate_normals(&vertices, &indices, options.normals);
+                    vertices = normal_vertices;
+                    indices = normal_indices;
+                    None
+                }
+            };
+
+            match tangents {
+                Some(tangents) => {
+                    for (v, tangent) in vertices.iter_mut().zip(tangents) {
+                        v.tangent = vec3(tangent[0], tangent[1], tangent[2]);
+                        v.binormal_headedness = tangent[3];
+                    }
+                }
+                None => {
+                    let (tangent_vertices, tangent_indices) = compute_tangents(&vertices, &indices);
+                    vertices = tangent_vertices;
+                    indices = tangent_indices;
+                }
+            }
+
+            let (vertices, indices, lods) = options.process_mesh(vertices, indices);
+
+            let material = primitive.material();
+            let pbr = material.pbr_metallic_roughness();
+
+            let slots = [
+                (
+                    "uAlbedoTexture",
+                    pbr.base_color_texture().map(|i| i.texture()),
+                ),
+                (
+                    "uMetallicRoughnessTexture",
+                    pbr.metallic_roughness_texture().map(|i| i.texture()),
+                ),
+                (
+                    "uNormalTexture",
+                    material.normal_texture().map(|i| i.texture()),
+                ),
+                (
+                    "uOcclusionTexture",
+                    material.occlusion_texture().map(|i| i.texture()),
+                ),
+                (
+                    "uEmissiveTexture",
+                    material.emissive_texture().map(|i| i.texture()),
+                ),
+            ];
+
+            let mut textures: Vec<TextureData> = Vec::new();
+            for (texture_type, texture) in slots.iter() {
+                if let Some(texture) = texture.as_ref().filter(|_| options.textures) {
+                    let image_index = texture.source().index();
+                    let key = format!("{}#image{}", key_prefix, image_index);
+
+                    textures.push(model.cached_texture(&key, texture_type, || {
+                        texture_from_image(filepath, &images[image_index])
+                    })?);
+                }
+            }
+
+            let mut mesh = Mesh::with_lods(vertices, indices, lods, textures);
+
+            // Only masked materials are alpha tested, whatever their albedo
+            // map's channels.
+            if material.alpha_mode() != gltf::material::AlphaMode::Mask {
+                mesh.features.remove(MaterialFeatures::HAS_ALPHA_MASK);
+            }
+
+            primitives.push(model.meshes.len());
+            model.meshes.push(mesh);
+        }
+
+        mesh_primitives.push(primitives);
+    }
+
+    // The model may already hold the nodes of other files.
+    let first_node = model.nodes.len();
+    model.nodes.extend(document.nodes().map(|node| {
+        ModelNode {
+            name: node.name().map(String::from),
+            transform: Matrix4::from(node.transform().matrix()),
+            children: node
+                .children()
+                .map(|child| first_node + child.index())
+                .collect(),
+            meshes: node
+                .mesh()
+                .map(|mesh| mesh_primitives[mesh.index()].clone())
+                .unwrap_or_default(),
+        }
+    }));
+
+    let roots = match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => scene
+            .nodes()
+            .map(|node| first_node + node.index())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(roots)
+}
+
+/// Uploads a decoded glTF image. 16-bit images are reduced to 8 bits per
+/// channel; floating point images are not supported.
+fn texture_from_image(filepath: &Path, image: &gltf::image::Data) -> Result<Texture> {
+    use gltf::image::Format;
+
+    let (channels, sixteen_bit) = match image.format {
+        Format::R8 => (1, false),
+        Format::R8G8 => (2, false),
+        Format::R8G8B8 => (3, false),
+        Format::R8G8B8A8 => (4, false),
+        Format::R16 => (1, true),
+        Format::R16G16 => (2, true),
+        Format::R16G16B16 => (3, true),
+        Format::R16G16B16A16 => (4, true),
+        format => {
+            return Err(Error::ImageDecode {
+                path: filepath.into(),
+                reason: format!("unsupported glTF image format {:?}", format),
+            })
+        }
+    };
+
+    let texture = if sixteen_bit {
+        // Little-endian samples, keep the high byte.
+        let pixels: Vec<u8> = image.pixels.chunks_exact(2).map(|s| s[1]).collect();
+        Texture::from_pixels(image.width, image.height, channels, &pixels)
+    } else {
+        Texture::from_pixels(image.width, image.height, channels, &image.pixels)
+    };
+    texture.map_err(|error| error.in_image(filepath))
+}
@@ -0,0 +1,97 @@
+use cgmath::*;
+
+use crate::{
+    Camera, Framebuffer, Model, ModelLodState, Renderer, Result, RgbaImage, Shader,
+    ShaderPermutations, Window,
+};
+
+const FOV: f32 = 50.0;
+
+/// Renders into an offscreen framebuffer using a hidden window's GL context,
+/// so frames can be produced without a visible display.
+pub struct HeadlessRenderer {
+    camera: Camera,
+    renderer: Renderer,
+    framebuffer: Framebuffer,
+    _window: Window,
+}
+
+impl HeadlessRenderer {
+    pub fn new(width: u32, height: u32) -> Result<HeadlessRenderer> {
+        let window = Window::new_hidden(width, height, "Tofu")?;
+        let framebuffer = Framebuffer::new(width, height)?;
+        let renderer = Renderer::new();
+
+        let mut camera = Camera::new();
+        camera.make_perspective(FOV, width as f32 / height as f32);
+
+        Ok(HeadlessRenderer {
+            camera,
+            renderer,
+            framebuffer,
+            _window: window,
+        })
+    }
+
+    pub fn camera(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    /// Renders one frame of `model`. Authored levels of detail are picked
+    /// afresh every time, so the image only depends on the camera and `time`.
+    pub fn render(
+        &mut self,
+        model: &Model,
+        shader: &Shader,
+        model_matrix: &Matrix4<f32>,
+        time: f32,
+    ) -> Result<RgbaImage> {
+        self.render_with(time, |renderer| {
+            renderer.draw_model(shader, model, model_matrix, &mut ModelLodState::default());
+            Ok(())
+        })
+    }
+
+    /// Like `render`, but picks a shader variant per mesh from `permutations`.
+    pub fn render_permutations(
+        &mut self,
+        model: &Model,
+        permutations: &mut ShaderPermutations,
+        model_matrix: &Matrix4<f32>,
+        time: f32,
+    ) -> Result<RgbaImage> {
+        self.render_with(time, |renderer| {
+            renderer.draw_model_permutations(
+                permutations,
+                model,
+                model_matrix,
+                &mut ModelLodState::default(),
+            )
+        })
+    }
+
+    fn render_with<F>(&mut self, time: f32, draw: F) -> Result<RgbaImage>
+    where
+        F: FnOnce(&mut Renderer) -> Result<()>,
+    {
+        unsafe {
+            self.framebuffer.bind();
+        }
+
+        self.renderer.set_viewport(
+            self.framebuffer.width() as i32,
+            self.framebuffer.height() as i32,
+        );
+        self.renderer.clear();
+        self.renderer.begin_frame(&self.camera, time);
+        let result = draw(&mut self.renderer);
+
+        unsafe {
+            gl::Finish();
+            self.framebuffer.unbind();
+        }
+
+        // The window's context stays current for the renderer's lifetime.
+        result.map(|_| unsafe { self.framebuffer.read_pixels() })
+    }
+}
@@ -0,0 +1,75 @@
+pub use cgmath;
+pub use glfw;
+
+mod error;
+pub use error::*;
+
+mod window;
+pub use window::*;
+
+mod renderer;
+pub use renderer::*;
+
+mod uniform_buffer;
+pub use uniform_buffer::*;
+
+mod storage_buffer;
+pub use storage_buffer::*;
+
+mod framebuffer;
+pub use framebuffer::*;
+
+mod rgba_image;
+pub use rgba_image::*;
+
+mod headless;
+pub use headless::*;
+
+mod shader;
+pub use shader::*;
+
+mod compute_shader;
+pub use compute_shader::*;
+
+mod permutations;
+pub use permutations::*;
+
+mod program_cache;
+pub use program_cache::*;
+
+mod preprocessor;
+pub use preprocessor::*;
+
+mod reflection;
+pub use reflection::*;
+
+mod texture;
+pub use texture::*;
+
+mod bounds;
+pub use bounds::*;
+
+mod frustum;
+pub use frustum::*;
+
+mod camera;
+pub use camera::*;
+
+mod mesh_cache;
+pub use mesh_cache::*;
+
+mod mesh_optimizer;
+pub use mesh_optimizer::*;
+
+mod mesh_simplifier;
+pub use mesh_simplifier::*;
+
+mod gltf_loader;
+mod ply_loader;
+mod stl_loader;
+
+mod mesh;
+pub use mesh::*;
+
+mod model;
+pub use model::*;
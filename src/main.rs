@@ -1,7 +1,202 @@
-mod tofu;
+mod app;
+
+use std::env;
+use std::process;
+
+use tofu::cgmath::Point3;
+
+const DEFAULT_MODEL: &str = "assets/models/3d_other_ufnscjdga/ufnscjdga_LOD0.obj";
+
+const USAGE: &str = "usage:
+    tofu [model]
+    tofu render <model> [--out frame.png] [--size 800x450]
+                        [--camera x,y,z] [--target x,y,z] [--time seconds]
+    tofu stats <model>";
+
+struct RenderArgs {
+    model_filepath: String,
+    output_filepath: String,
+    width: u32,
+    height: u32,
+    camera: Point3<f32>,
+    target: Point3<f32>,
+    time: f32,
+}
 
 pub fn main() {
-    let mut app = tofu::App::new();
+    // The library and the viewer report recoverable problems, like unknown
+    // uniforms or failed shader reloads, through `log`; reloads are logged at
+    // info level.
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn,tofu=info"))
+        .init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(String::as_str) {
+        Some("render") => match parse_render_args(&args[1..]) {
+            Ok(render_args) => render(&render_args),
+            Err(message) => {
+                eprintln!("tofu: {}\n{}", message, USAGE);
+                process::exit(2);
+            }
+        },
+        Some("stats") => match &args[1..] {
+            [model_filepath] => stats(model_filepath),
+            _ => {
+                eprintln!("tofu: expected one model path\n{}", USAGE);
+                process::exit(2);
+            }
+        },
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            return;
+        }
+        model_filepath => {
+            let mut app = app::App::new(model_filepath.unwrap_or(DEFAULT_MODEL));
+            app.run()
+        }
+    };
+
+    if let Err(error) = result {
+        eprintln!("tofu: {}", error);
+        process::exit(1);
+    }
+}
+
+fn render(args: &RenderArgs) -> tofu::Result<()> {
+    let mut headless = tofu::HeadlessRenderer::new(args.width, args.height)?;
+
+    let mut permutations = tofu::ShaderPermutations::new(
+        tofu::ShaderBuilder::new()
+            .vertex("assets/shaders/basic.vs")
+            .fragment("assets/shaders/basic.fs"),
+    );
+    let model = tofu::Model::new(&args.model_filepath)?;
+    for warning in &model.warnings {
+        log::warn!("{}", warning);
+    }
+
+    headless.camera().set_position(args.camera);
+    headless.camera().look_at(args.target);
+
+    let image = headless.render_permutations(
+        &model,
+        &mut permutations,
+        &tofu::turntable_matrix(args.time),
+        args.time,
+    )?;
+
+    image.save_png(&args.output_filepath)
+}
+
+/// Prints vertex cache statistics of every mesh as imported and after
+/// `optimize_mesh`.
+fn stats(model_filepath: &str) -> tofu::Result<()> {
+    // Only needed because loading a model uploads its meshes.
+    let _context = tofu::HeadlessRenderer::new(1, 1)?;
+
+    let options = tofu::LoadOptions {
+        optimize: false,
+        mesh_cache: None,
+        textures: false,
+        ..tofu::LoadOptions::default()
+    };
+    let model = tofu::Model::with_options(model_filepath, &options)?;
+
+    for (i, mesh) in model.meshes.iter().enumerate() {
+        let before =
+            tofu::analyze_vertex_cache(&mesh.indices, mesh.vertices.len(), tofu::VERTEX_CACHE_SIZE);
+        let (vertices, indices) = tofu::optimize_mesh(&mesh.vertices, &mesh.indices);
+        let after = tofu::analyze_vertex_cache(&indices, vertices.len(), tofu::VERTEX_CACHE_SIZE);
+
+        println!(
+            "mesh {}: {} triangles, {} -> {} vertices\n    imported:  {}\n    optimized: {}",
+            i,
+            mesh.indices.len() / 3,
+            mesh.vertices.len(),
+            vertices.len(),
+            before,
+            after
+        );
+    }
+
+    Ok(())
+}
+
+fn parse_render_args(args: &[String]) -> Result<RenderArgs, String> {
+    let mut render_args = RenderArgs {
+        model_filepath: String::new(),
+        output_filepath: "frame.png".into(),
+        width: 800,
+        height: 450,
+        camera: Point3::new(0.0, 1.0, 7.0),
+        target: Point3::new(0.0, 1.0, 0.0),
+        time: 0.0,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {}", arg))
+        };
+
+        match arg.as_str() {
+            "--out" => render_args.output_filepath = value()?.clone(),
+            "--size" => {
+                let size = value()?;
+                let (width, height) = parse_size(size)
+                    .ok_or_else(|| format!("invalid size '{}', expected WxH", size))?;
+                render_args.width = width;
+                render_args.height = height;
+            }
+            "--camera" => render_args.camera = parse_point(value()?)?,
+            "--target" => render_args.target = parse_point(value()?)?,
+            "--time" => {
+                let time = value()?;
+                render_args.time = time
+                    .parse()
+                    .map_err(|_| format!("invalid time '{}'", time))?;
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            model_filepath if render_args.model_filepath.is_empty() => {
+                render_args.model_filepath = model_filepath.into()
+            }
+            extra => return Err(format!("unexpected argument '{}'", extra)),
+        }
+    }
+
+    if render_args.model_filepath.is_empty() {
+        return Err("missing model path".into());
+    }
+    if render_args.camera == render_args.target {
+        return Err("the camera and the target are the same point".into());
+    }
+
+    Ok(render_args)
+}
+
+fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let mut parts = size.split('x');
+    let width = parts.next()?.parse().ok()?;
+    let height = parts.next()?.parse().ok()?;
+
+    if parts.next().is_some() || width == 0 || height == 0 {
+        return None;
+    }
+
+    Some((width, height))
+}
+
+fn parse_point(point: &str) -> Result<Point3<f32>, String> {
+    let coordinates: Vec<f32> = point
+        .split(',')
+        .map(|c| c.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("invalid point '{}', expected x,y,z", point))?;
 
-    app.run();
+    match coordinates[..] {
+        [x, y, z] if [x, y, z].iter().all(|c| c.is_finite()) => Ok(Point3::new(x, y, z)),
+        _ => Err(format!("invalid point '{}', expected x,y,z", point)),
+    }
 }
@@ -0,0 +1,528 @@
+use cgmath::prelude::*;
+use cgmath::{vec3, Deg, Point3, Vector2, Vector3, Vector4};
+
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::mem;
+use std::rc::Rc;
+
+use gl::types::*;
+
+use crate::{Bounds, MaterialFeatures, Shader, ShaderStage, Texture};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Vertex {
+    pub position: Vector3<f32>,
+    pub normal: Vector3<f32>,
+    pub uv: Vector2<f32>,
+    pub tangent: Vector3<f32>,
+    pub binormal_headedness: f32,
+    /// Linear RGBA, white unless the source file has vertex colors.
+    pub color: Vector4<f32>,
+}
+
+/// Describes one vertex attribute as it is laid out in the vertex buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub location: u32,
+    pub components: i32,
+    pub offset: usize,
+}
+
+impl Vertex {
+    pub const LAYOUT: &'static [VertexAttribute] = &[
+        // Position
+        VertexAttribute {
+            location: 0,
+            components: 3,
+            offset: 0,
+        },
+        // Normal
+        VertexAttribute {
+            location: 1,
+            components: 3,
+            offset: 3 * mem::size_of::<GLfloat>(),
+        },
+        // UV
+        VertexAttribute {
+            location: 2,
+            components: 2,
+            offset: 6 * mem::size_of::<GLfloat>(),
+        },
+        // Tangent and binormal headedness
+        VertexAttribute {
+            location: 3,
+            components: 4,
+            offset: 8 * mem::size_of::<GLfloat>(),
+        },
+        // Color
+        VertexAttribute {
+            location: 4,
+            components: 4,
+            offset: 12 * mem::size_of::<GLfloat>(),
+        },
+    ];
+}
+
+impl Default for Vertex {
+    fn default() -> Vertex {
+        Vertex {
+            position: Vector3::zero(),
+            normal: Vector3::zero(),
+            uv: Vector2::zero(),
+            tangent: Vector3::zero(),
+            binormal_headedness: 1.0,
+            color: Vector4::new(1.0, 1.0, 1.0, 1.0),
+        }
+    }
+}
+
+/// A texture bound to one of a mesh's material slots. Meshes loaded from the
+/// same file share the textures in `Model::textures_loaded`.
+#[derive(Clone)]
+pub struct TextureData {
+    pub texture: Rc<Texture>,
+    pub texture_type: String,
+    pub filepath: String,
+}
+
+/// A simplified level of detail, drawn from the same vertices as the full
+/// mesh.
+#[derive(Debug, Clone)]
+pub struct MeshLod {
+    pub indices: Vec<u32>,
+    /// Largest distance the surface moved, in model space units.
+    pub error: f32,
+}
+
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    /// Coarser levels following the full mesh, in order.
+    pub lods: Vec<MeshLod>,
+    pub textures: Vec<TextureData>,
+    pub features: MaterialFeatures,
+    bounds: Bounds,
+    vao: GLuint,
+    vbo: GLuint,
+    ebo: GLuint,
+}
+
+impl Mesh {
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>, textures: Vec<TextureData>) -> Mesh {
+        Mesh::with_lods(vertices, indices, Vec::new(), textures)
+    }
+
+    pub fn with_lods(
+        vertices: Vec<Vertex>,
+        indices: Vec<u32>,
+        lods: Vec<MeshLod>,
+        textures: Vec<TextureData>,
+    ) -> Mesh {
+        let features = MaterialFeatures::from_textures(&textures);
+
+        let bounds = Bounds::from_points(vertices.iter().map(|v| Point3::from_vec(v.position)))
+            .unwrap_or_else(|| Bounds::point(Point3::origin()));
+
+        let mut mesh = Mesh {
+            vertices,
+            indices,
+            lods,
+            textures,
+            features,
+            bounds,
+            vao: 0,
+            vbo: 0,
+            ebo: 0,
+        };
+
+        // Empty meshes, such as OBJ groups without faces, have nothing to upload.
+        if !mesh.vertices.is_empty() && !mesh.indices.is_empty() {
+            unsafe {
+                mesh.setup_mesh();
+            }
+        }
+
+        mesh
+    }
+
+    /// Number of levels of detail including the full mesh.
+    pub fn lod_count(&self) -> usize {
+        self.lods.len() + 1
+    }
+
+    /// Bounds around every vertex in model space, empty at the origin for
+    /// meshes without vertices.
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the mesh was created on.
+    pub unsafe fn draw(&self, shader: &Shader) {
+        self.draw_lod(shader, 0);
+    }
+
+    /// Draws level `lod`, where 0 is the full mesh and 1 the first of `lods`.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the mesh was created on.
+    pub unsafe fn draw_lod(&self, shader: &Shader, lod: usize) {
+        if self.vao == 0 {
+            return;
+        }
+
+        shader.validate_vertex_layout(Vertex::LAYOUT);
+
+        let mut unit = 0;
+        for texture_data in &self.textures {
+            if shader
+                .reflection()
+                .sampler(&texture_data.texture_type)
+                .is_none()
+            {
+                continue;
+            }
+
+            shader.set_int(&texture_data.texture_type, unit as i32);
+            texture_data.texture.bind(gl::TEXTURE0 + unit);
+            unit += 1;
+        }
+
+        let mode = if shader.has_stage(ShaderStage::TessEvaluation) {
+            gl::PatchParameteri(gl::PATCH_VERTICES, 3);
+            gl::PATCHES
+        } else {
+            gl::TRIANGLES
+        };
+
+        // Levels are stored one after another in the index buffer.
+        let (first_index, count) = match lod.min(self.lods.len()) {
+            0 => (0, self.indices.len()),
+            lod => (
+                self.indices.len()
+                    + self.lods[..lod - 1]
+                        .iter()
+                        .map(|l| l.indices.len())
+                        .sum::<usize>(),
+                self.lods[lod - 1].indices.len(),
+            ),
+        };
+
+        gl::BindVertexArray(self.vao);
+        gl::DrawElements(
+            mode,
+            count as GLsizei,
+            gl::UNSIGNED_INT,
+            (first_index * mem::size_of::<u32>()) as *const c_void,
+        );
+        gl::BindVertexArray(0);
+
+        gl::ActiveTexture(gl::TEXTURE0);
+    }
+
+    unsafe fn setup_mesh(&mut self) {
+        gl::GenVertexArrays(1, &mut self.vao);
+        gl::GenBuffers(1, &mut self.vbo);
+        gl::GenBuffers(1, &mut self.ebo);
+
+        gl::BindVertexArray(self.vao);
+
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+        let size = (self.vertices.len() * mem::size_of::<Vertex>()) as GLsizeiptr;
+        let data = &self.vertices[0] as *const Vertex as *const c_void;
+        gl::BufferData(gl::ARRAY_BUFFER, size, data, gl::STATIC_DRAW);
+
+        let mut indices = self.indices.clone();
+        for lod in &self.lods {
+            indices.extend_from_slice(&lod.indices);
+        }
+
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
+        let size = (indices.len() * mem::size_of::<u32>()) as GLsizeiptr;
+        let data = &indices[0] as *const u32 as *const c_void;
+        gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, size, data, gl::STATIC_DRAW);
+
+        for attribute in Vertex::LAYOUT {
+            gl::EnableVertexAttribArray(attribute.location);
+            gl::VertexAttribPointer(
+                attribute.location,
+                attribute.components,
+                gl::FLOAT,
+                gl::FALSE,
+                mem::size_of::<Vertex>() as GLsizei,
+                attribute.offset as *const c_void,
+            );
+        }
+
+        gl::BindVertexArray(0);
+    }
+}
+
+impl Drop for Mesh {
+    fn drop(&mut self) {
+        if self.vao == 0 {
+            return;
+        }
+
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteBuffers(1, &self.ebo);
+        }
+    }
+}
+
+/// How normals are generated for meshes that come without them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalGeneration {
+    /// Averages the normals of adjacent faces that meet at less than
+    /// `crease_angle` degrees. Vertices on sharper edges are split.
+    Smooth { crease_angle: f32 },
+    /// Every face gets its own normal.
+    Flat,
+}
+
+impl Default for NormalGeneration {
+    fn default() -> NormalGeneration {
+        NormalGeneration::Smooth { crease_angle: 60.0 }
+    }
+}
+
+/// Generates normals for an indexed mesh, returning new vertices and indices
+/// since vertices on creases have to be split.
+///
+/// Faces are considered adjacent when they share a vertex position, so
+/// smoothing also works across UV seams.
+pub fn generate_normals(
+    vertices: &[Vertex],
+    indices: &[u32],
+    generation: NormalGeneration,
+) -> (Vec<Vertex>, Vec<u32>) {
+    // Area-weighted, the cross product's length is twice the triangle's area.
+    let face_normals: Vec<Vector3<f32>> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let p0 = vertices[triangle[0] as usize].position;
+            let p1 = vertices[triangle[1] as usize].position;
+            let p2 = vertices[triangle[2] as usize].position;
+            (p1 - p0).cross(p2 - p0)
+        })
+        .collect();
+    let face_directions: Vec<Vector3<f32>> = face_normals
+        .iter()
+        .map(|&n| normalize_or(n, Vector3::zero()))
+        .collect();
+
+    let mut faces_at_position: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    if let NormalGeneration::Smooth { .. } = generation {
+        for (face, triangle) in indices.chunks_exact(3).enumerate() {
+            for &index in triangle {
+                let key = vector_key(vertices[index as usize].position);
+                faces_at_position.entry(key).or_default().push(face);
+            }
+        }
+    }
+
+    let mut output_vertices: Vec<Vertex> = Vec::with_capacity(vertices.len());
+    let mut output_indices = Vec::with_capacity(indices.len());
+    let mut emitted: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+
+    for (face, triangle) in indices.chunks_exact(3).enumerate() {
+        for &index in triangle {
+            let vertex = &vertices[index as usize];
+
+            let normal = match generation {
+                NormalGeneration::Smooth { crease_angle } => {
+                    let min_cos = Deg(crease_angle).cos();
+
+                    let mut normal = Vector3::zero();
+                    for &other in &faces_at_position[&vector_key(vertex.position)] {
+                        if other == face
+                            || face_directions[face].dot(face_directions[other]) >= min_cos
+                        {
+                            normal += face_normals[other];
+                        }
+                    }
+                    normal
+                }
+                NormalGeneration::Flat => face_normals[face],
+            };
+            let normal = normalize_or(normal, Vector3::unit_z());
+
+            let key = (index, vector_key(normal));
+            let output_index = *emitted.entry(key).or_insert_with(|| {
+                output_vertices.push(Vertex { normal, ..*vertex });
+                (output_vertices.len() - 1) as u32
+            });
+            output_indices.push(output_index);
+        }
+    }
+
+    (output_vertices, output_indices)
+}
+
+/// Replaces the normals with area-weighted averages of the adjacent face
+/// normals. Vertices shared between faces get smooth normals.
+pub fn compute_normals(vertices: &mut [Vertex], indices: &[u32]) {
+    for v in vertices.iter_mut() {
+        v.normal = Vector3::zero();
+    }
+
+    for triangle in indices.chunks_exact(3) {
+        let (i0, i1, i2) = (
+            triangle[0] as usize,
+            triangle[1] as usize,
+            triangle[2] as usize,
+        );
+
+        let (p0, p1, p2) = (
+            vertices[i0].position,
+            vertices[i1].position,
+            vertices[i2].position,
+        );
+
+        // The cross product's length is twice the triangle's area.
+        let face_normal = (p1 - p0).cross(p2 - p0);
+
+        vertices[i0].normal += face_normal;
+        vertices[i1].normal += face_normal;
+        vertices[i2].normal += face_normal;
+    }
+
+    for v in vertices.iter_mut() {
+        v.normal = normalize_or(v.normal, Vector3::unit_z());
+    }
+}
+
+/// Picks an arbitrary tangent perpendicular to each normal, for meshes
+/// without UVs.
+pub fn compute_orthonormal_tangents(vertices: &mut [Vertex]) {
+    for v in vertices.iter_mut() {
+        v.tangent = orthonormal_tangent(v.normal);
+        v.binormal_headedness = 1.0;
+    }
+}
+
+/// Generates MikkTSpace tangents, matching what Blender, Substance and most
+/// other baking tools produce, so baked normal maps line up without seams.
+///
+/// Tangents are computed per triangle corner, so vertices whose corners end
+/// up with different tangents or handedness are split, returning new
+/// vertices and indices. Corners without a usable tangent get an arbitrary
+/// one perpendicular to the normal.
+pub fn compute_tangents(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut geometry = TangentGeometry {
+        vertices,
+        indices,
+        tangents: vec![None; indices.len() / 3 * 3],
+    };
+    mikktspace::generate_tangents(&mut geometry);
+
+    let mut output_vertices: Vec<Vertex> = Vec::with_capacity(vertices.len());
+    let mut output_indices = Vec::with_capacity(geometry.tangents.len());
+    let mut emitted: HashMap<(u32, [u32; 3], bool), u32> = HashMap::new();
+
+    for (&index, tangent) in indices.iter().zip(&geometry.tangents) {
+        let vertex = &vertices[index as usize];
+
+        let (tangent, headedness) = match *tangent {
+            Some([x, y, z, w]) if vec3(x, y, z).magnitude2() > EPSILON => {
+                (vec3(x, y, z).normalize(), if w < 0.0 { -1.0 } else { 1.0 })
+            }
+            _ => (orthonormal_tangent(vertex.normal), 1.0),
+        };
+
+        let key = (index, vector_key(tangent), headedness < 0.0);
+        let output_index = *emitted.entry(key).or_insert_with(|| {
+            output_vertices.push(Vertex {
+                tangent,
+                binormal_headedness: headedness,
+                ..*vertex
+            });
+            (output_vertices.len() - 1) as u32
+        });
+        output_indices.push(output_index);
+    }
+
+    (output_vertices, output_indices)
+}
+
+/// Exposes an indexed triangle list to `mikktspace`, collecting the tangent
+/// of every corner.
+struct TangentGeometry<'a> {
+    vertices: &'a [Vertex],
+    indices: &'a [u32],
+    tangents: Vec<Option<[f32; 4]>>,
+}
+
+impl<'a> TangentGeometry<'a> {
+    fn vertex(&self, face: usize, vert: usize) -> &Vertex {
+        &self.vertices[self.indices[face * 3 + vert] as usize]
+    }
+}
+
+impl<'a> mikktspace::Geometry for TangentGeometry<'a> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).position.into()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.vertex(face, vert).normal.into()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        // MikkTSpace expects V pointing up, our UVs have their origin at the
+        // top left.
+        let uv = self.vertex(face, vert).uv;
+        [uv.x, 1.0 - uv.y]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        if tangent.iter().all(|c| c.is_finite()) {
+            self.tangents[face * 3 + vert] = Some(tangent);
+        }
+    }
+}
+
+/// Squared length below which vectors are treated as zero.
+const EPSILON: f32 = 1e-12;
+
+fn normalize_or(v: Vector3<f32>, fallback: Vector3<f32>) -> Vector3<f32> {
+    if v.magnitude2() > EPSILON {
+        v.normalize()
+    } else {
+        fallback
+    }
+}
+
+/// Projects the axis least aligned with the normal onto its plane.
+fn orthonormal_tangent(normal: Vector3<f32>) -> Vector3<f32> {
+    let axis = if normal.x.abs() < 0.9 {
+        Vector3::unit_x()
+    } else {
+        Vector3::unit_y()
+    };
+
+    normalize_or(axis - normal * axis.dot(normal), Vector3::unit_x())
+}
+
+/// Bit pattern of a vector for hashing, with -0.0 folded into 0.0.
+fn vector_key(v: Vector3<f32>) -> [u32; 3] {
+    [
+        (v.x + 0.0).to_bits(),
+        (v.y + 0.0).to_bits(),
+        (v.z + 0.0).to_bits(),
+    ]
+}
@@ -0,0 +1,569 @@
+use memmap2::Mmap;
+
+use std::env;
+use std::fs::{self, File, Metadata};
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::UNIX_EPOCH;
+
+use crate::mesh::{MeshLod, Vertex};
+use crate::program_cache::{fnv1a, FNV_OFFSET_BASIS};
+
+const MAGIC: &[u8; 8] = b"TOFUMESH";
+/// Bumped whenever the format or the meshes the OBJ import produces change.
+const VERSION: u32 = 3;
+const NO_MATERIAL: u32 = u32::MAX;
+/// Magic, version and options key.
+const HEADER_LENGTH: u64 = MAGIC.len() as u64 + 4 + 8;
+
+/// Stores imported OBJ meshes on disk in a compact binary format, so later
+/// runs can skip parsing and tangent generation.
+///
+/// An entry records the modification time, size and hash of the OBJ file and
+/// of every MTL library it references, and is ignored once any of them
+/// changes. Sources are only read and hashed again when their size or
+/// modification time differs. Entries are memory-mapped when read. Vertex and
+/// index blobs are stored as they are laid out in memory, so entries are not
+/// meant to be shared between machines.
+///
+/// An entry is laid out as, with all other integers little-endian:
+///
+/// - header: magic `TOFUMESH`, format version, hash of the load options
+/// - sources: path, modification time, size and hash of every source file
+/// - vertex layout: vertex size and every `Vertex::LAYOUT` attribute
+/// - material table: texture slot and file of every material's textures
+/// - meshes: material index, vertex and index counts, both blobs, then the
+///   error, index count and index blob of every level of detail
+#[derive(Debug, Clone)]
+pub struct MeshCache {
+    directory: PathBuf,
+}
+
+/// A model's meshes before any GL resources are created.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CachedModel {
+    pub meshes: Vec<CachedMesh>,
+    pub materials: Vec<CachedMaterial>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedMesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub lods: Vec<MeshLod>,
+    /// Index into `CachedModel::materials`.
+    pub material: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CachedMaterial {
+    /// Texture slot and file name relative to the model, for every texture.
+    pub textures: Vec<(String, String)>,
+}
+
+/// Identifies the contents of a source file at the time it was imported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SourceStamp {
+    path: String,
+    modified: u64,
+    size: u64,
+    hash: u64,
+}
+
+impl MeshCache {
+    pub fn new<P: AsRef<Path>>(directory: P) -> MeshCache {
+        MeshCache {
+            directory: directory.as_ref().into(),
+        }
+    }
+
+    /// `tofu/meshes` in the system's temporary directory.
+    pub fn default_directory() -> PathBuf {
+        env::temp_dir().join("tofu").join("meshes")
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Reads the entry for `filepath` imported with options hashing to
+    /// `options_key`, if there is one and its sources have not changed.
+    pub(crate) fn load(&self, filepath: &Path, options_key: u64) -> Option<CachedModel> {
+        let file = File::open(self.entry_filepath(filepath, options_key)).ok()?;
+        if file.metadata().ok()?.len() < HEADER_LENGTH {
+            return None;
+        }
+        // Safety: entries are only ever replaced by renaming, never modified
+        // in place, so the mapped contents cannot change under us. Every read
+        // below is bounds-checked against the mapped length.
+        let data = unsafe { Mmap::map(&file) }.ok()?;
+
+        let mut reader = Reader {
+            data: &data,
+            position: 0,
+        };
+        if reader.bytes(MAGIC.len())? != MAGIC
+            || reader.u32()? != VERSION
+            || reader.u64()? != options_key
+        {
+            return None;
+        }
+
+        let mut sources = Vec::new();
+        for _ in 0..reader.u32()? {
+            sources.push(SourceStamp {
+                path: reader.string()?,
+                modified: reader.u64()?,
+                size: reader.u64()?,
+                hash: reader.u64()?,
+            });
+        }
+        if !sources_unchanged(&sources) {
+            return None;
+        }
+
+        if reader.u32()? as usize != mem::size_of::<Vertex>()
+            || reader.u32()? as usize != Vertex::LAYOUT.len()
+        {
+            return None;
+        }
+        for attribute in Vertex::LAYOUT {
+            if reader.u32()? != attribute.location
+                || reader.u32()? as i32 != attribute.components
+                || reader.u32()? as usize != attribute.offset
+            {
+                return None;
+            }
+        }
+
+        let mut model = CachedModel::default();
+        for _ in 0..reader.u32()? {
+            let mut material = CachedMaterial::default();
+            for _ in 0..reader.u32()? {
+                material.textures.push((reader.string()?, reader.string()?));
+            }
+            model.materials.push(material);
+        }
+
+        for _ in 0..reader.u32()? {
+            let material = match reader.u32()? {
+                NO_MATERIAL => None,
+                index if (index as usize) < model.materials.len() => Some(index as usize),
+                _ => return None,
+            };
+            let vertex_count = reader.u32()? as usize;
+            let index_count = reader.u32()? as usize;
+
+            let vertices: Vec<Vertex> = reader.array(vertex_count)?;
+            let indices: Vec<u32> = reader.array(index_count)?;
+            if indices.iter().any(|&i| i as usize >= vertices.len()) {
+                return None;
+            }
+
+            let mut lods = Vec::new();
+            for _ in 0..reader.u32()? {
+                let error = f32::from_bits(reader.u32()?);
+                let index_count = reader.u32()? as usize;
+                let indices: Vec<u32> = reader.array(index_count)?;
+                if indices.iter().any(|&i| i as usize >= vertices.len()) {
+                    return None;
+                }
+                lods.push(MeshLod { indices, error });
+            }
+
+            model.meshes.push(CachedMesh {
+                vertices,
+                indices,
+                lods,
+                material,
+            });
+        }
+
+        Some(model)
+    }
+
+    /// Writes the entry for `filepath` with the `sources` it was imported from,
+    /// as returned by `source_stamps` before importing.
+    pub(crate) fn store(
+        &self,
+        filepath: &Path,
+        options_key: u64,
+        sources: &[SourceStamp],
+        model: &CachedModel,
+    ) -> io::Result<()> {
+        let mut writer = Writer::default();
+        writer.bytes(MAGIC);
+        writer.u32(VERSION);
+        writer.u64(options_key);
+
+        writer.u32(sources.len() as u32);
+        for source in sources {
+            writer.string(&source.path);
+            writer.u64(source.modified);
+            writer.u64(source.size);
+            writer.u64(source.hash);
+        }
+
+        writer.u32(mem::size_of::<Vertex>() as u32);
+        writer.u32(Vertex::LAYOUT.len() as u32);
+        for attribute in Vertex::LAYOUT {
+            writer.u32(attribute.location);
+            writer.u32(attribute.components as u32);
+            writer.u32(attribute.offset as u32);
+        }
+
+        writer.u32(model.materials.len() as u32);
+        for material in &model.materials {
+            writer.u32(material.textures.len() as u32);
+            for (texture_type, texture_filepath) in &material.textures {
+                writer.string(texture_type);
+                writer.string(texture_filepath);
+            }
+        }
+
+        writer.u32(model.meshes.len() as u32);
+        for mesh in &model.meshes {
+            writer.u32(mesh.material.map_or(NO_MATERIAL, |m| m as u32));
+            writer.u32(mesh.vertices.len() as u32);
+            writer.u32(mesh.indices.len() as u32);
+            writer.array(&mesh.vertices);
+            writer.array(&mesh.indices);
+
+            writer.u32(mesh.lods.len() as u32);
+            for lod in &mesh.lods {
+                writer.u32(lod.error.to_bits());
+                writer.u32(lod.indices.len() as u32);
+                writer.array(&lod.indices);
+            }
+        }
+
+        // Written under a temporary name first so concurrent runs never see a
+        // partially written entry, and mapped entries are never modified.
+        fs::create_dir_all(&self.directory)?;
+        let entry_filepath = self.entry_filepath(filepath, options_key);
+        let temporary_filepath =
+            entry_filepath.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temporary_filepath, &writer.data)?;
+        fs::rename(&temporary_filepath, &entry_filepath)
+    }
+
+    fn entry_filepath(&self, filepath: &Path, options_key: u64) -> PathBuf {
+        let filepath = fs::canonicalize(filepath).unwrap_or_else(|_| filepath.into());
+        let hash = fnv1a(FNV_OFFSET_BASIS, filepath.to_string_lossy().as_bytes());
+        let hash = fnv1a(hash, &options_key.to_le_bytes());
+        self.directory.join(format!("{:016x}.mesh", hash))
+    }
+}
+
+impl Default for MeshCache {
+    fn default() -> MeshCache {
+        MeshCache::new(MeshCache::default_directory())
+    }
+}
+
+/// Stamps of the OBJ file and every MTL library it references. Missing
+/// libraries are recorded too, so creating one invalidates the entry.
+///
+/// Taken before importing, so a source that changes while it is parsed leaves
+/// an entry that no longer matches instead of one recording the new contents.
+pub(crate) fn source_stamps(filepath: &Path) -> io::Result<Vec<SourceStamp>> {
+    let data = fs::read(filepath)?;
+    let mut stamps = vec![stamp(filepath, &data)?];
+
+    let directory = filepath.parent().unwrap_or_else(|| Path::new(""));
+    for line in String::from_utf8_lossy(&data).lines() {
+        let mut words = line.trim_start().splitn(2, char::is_whitespace);
+        if let (Some("mtllib"), Some(name)) = (words.next(), words.next()) {
+            let mtl_filepath = directory.join(name.trim());
+            stamps.push(match fs::read(&mtl_filepath) {
+                Ok(data) => stamp(&mtl_filepath, &data)?,
+                Err(_) => SourceStamp {
+                    path: mtl_filepath.to_string_lossy().into(),
+                    modified: 0,
+                    size: 0,
+                    hash: 0,
+                },
+            });
+        }
+    }
+
+    Ok(stamps)
+}
+
+fn stamp(filepath: &Path, data: &[u8]) -> io::Result<SourceStamp> {
+    Ok(SourceStamp {
+        path: filepath.to_string_lossy().into(),
+        modified: modified_nanos(&fs::metadata(filepath)?)?,
+        size: data.len() as u64,
+        hash: fnv1a(FNV_OFFSET_BASIS, data),
+    })
+}
+
+/// Whether every source still has the contents it was stamped with. Files
+/// whose size and modification time match are not read.
+fn sources_unchanged(stamps: &[SourceStamp]) -> bool {
+    stamps.iter().all(|stamp| {
+        let filepath = Path::new(&stamp.path);
+        let metadata = match fs::metadata(filepath) {
+            Ok(metadata) => metadata,
+            // Libraries that were missing have to stay missing.
+            Err(_) => return stamp.modified == 0 && stamp.size == 0 && stamp.hash == 0,
+        };
+
+        if metadata.len() != stamp.size {
+            return false;
+        }
+        if modified_nanos(&metadata).ok() == Some(stamp.modified) {
+            return true;
+        }
+
+        // Touched, e.g. by a checkout, but possibly with the same contents.
+        fs::read(filepath).is_ok_and(|data| fnv1a(FNV_OFFSET_BASIS, &data) == stamp.hash)
+    })
+}
+
+fn modified_nanos(metadata: &Metadata) -> io::Result<u64> {
+    Ok(metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, length: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.checked_add(length)?)?;
+        self.position += length;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let b = self.bytes(4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let b = self.bytes(8)?;
+        Some(u64::from_le_bytes([
+            b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7],
+        ]))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = self.u32()? as usize;
+        String::from_utf8(self.bytes(length)?.to_vec()).ok()
+    }
+
+    /// Copies `count` plain values, which need not be aligned in the file.
+    /// Only used for `Vertex` and `u32`, for which every bit pattern is valid.
+    fn array<T: Copy>(&mut self, count: usize) -> Option<Vec<T>> {
+        let bytes = self.bytes(count.checked_mul(mem::size_of::<T>())?)?;
+        let mut values = Vec::with_capacity(count);
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), values.as_mut_ptr() as *mut u8, bytes.len());
+            values.set_len(count);
+        }
+        Some(values)
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.bytes(value.as_bytes());
+    }
+
+    fn array<T: Copy>(&mut self, values: &[T]) {
+        let bytes = unsafe {
+            std::slice::from_raw_parts(values.as_ptr() as *const u8, mem::size_of_val(values))
+        };
+        self.bytes(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cgmath::vec3;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+
+    const OBJ: &str = "mtllib cube.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n";
+
+    /// A cache and a model directory with an OBJ and its library, both fresh
+    /// for every test.
+    fn setup(test: &str) -> (MeshCache, PathBuf) {
+        let directory = env::temp_dir().join("tofu-mesh-cache-tests").join(test);
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(directory.join("model")).unwrap();
+
+        let filepath = directory.join("model").join("cube.obj");
+        fs::write(&filepath, OBJ).unwrap();
+        fs::write(directory.join("model").join("cube.mtl"), "newmtl cube\n").unwrap();
+
+        (MeshCache::new(directory.join("cache")), filepath)
+    }
+
+    fn model() -> CachedModel {
+        let vertex = |x, y| Vertex {
+            position: vec3(x, y, 0.0),
+            normal: vec3(0.0, 0.0, 1.0),
+            ..Vertex::default()
+        };
+
+        CachedModel {
+            meshes: vec![CachedMesh {
+                vertices: vec![vertex(0.0, 0.0), vertex(1.0, 0.0), vertex(0.0, 1.0)],
+                indices: vec![0, 1, 2],
+                lods: vec![MeshLod {
+                    indices: vec![0, 2, 1],
+                    error: 0.5,
+                }],
+                material: Some(0),
+            }],
+            materials: vec![CachedMaterial {
+                textures: vec![("uAlbedoTexture".into(), "cube.png".into())],
+            }],
+        }
+    }
+
+    fn store(cache: &MeshCache, filepath: &Path) {
+        let sources = source_stamps(filepath).unwrap();
+        cache.store(filepath, 7, &sources, &model()).unwrap();
+    }
+
+    fn set_modified(filepath: &Path, modified: SystemTime) {
+        File::options()
+            .write(true)
+            .open(filepath)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn round_trip() {
+        let (cache, filepath) = setup("round_trip");
+        store(&cache, &filepath);
+
+        let loaded = cache.load(&filepath, 7).unwrap();
+        let mesh = &loaded.meshes[0];
+        let positions: Vec<_> = mesh.vertices.iter().map(|v| v.position).collect();
+        assert_eq!(
+            positions,
+            [
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0)
+            ]
+        );
+        assert!(mesh
+            .vertices
+            .iter()
+            .all(|v| v.normal == vec3(0.0, 0.0, 1.0)));
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert_eq!(mesh.lods[0].indices, [0, 2, 1]);
+        assert_eq!(mesh.lods[0].error, 0.5);
+        assert_eq!(mesh.material, Some(0));
+        assert_eq!(
+            loaded.materials[0].textures,
+            [("uAlbedoTexture".to_string(), "cube.png".to_string())]
+        );
+
+        assert!(cache.load(&filepath, 8).is_none());
+    }
+
+    #[test]
+    fn magic_and_version_mismatches_are_ignored() {
+        let (cache, filepath) = setup("header_mismatch");
+        store(&cache, &filepath);
+        let entry_filepath = cache.entry_filepath(&filepath, 7);
+        let entry = fs::read(&entry_filepath).unwrap();
+
+        let mut wrong_magic = entry.clone();
+        wrong_magic[0] ^= 1;
+        fs::write(&entry_filepath, &wrong_magic).unwrap();
+        assert!(cache.load(&filepath, 7).is_none());
+
+        let mut wrong_version = entry.clone();
+        wrong_version[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        fs::write(&entry_filepath, &wrong_version).unwrap();
+        assert!(cache.load(&filepath, 7).is_none());
+
+        fs::write(&entry_filepath, &entry[..entry.len() - 1]).unwrap();
+        assert!(cache.load(&filepath, 7).is_none());
+
+        fs::write(&entry_filepath, []).unwrap();
+        assert!(cache.load(&filepath, 7).is_none());
+    }
+
+    #[test]
+    fn changed_sources_invalidate_entries() {
+        let (cache, filepath) = setup("invalidation");
+        let mtl_filepath = filepath.with_extension("mtl");
+        store(&cache, &filepath);
+
+        // Same contents under a new modification time are still valid.
+        set_modified(&filepath, SystemTime::now() + Duration::from_secs(60));
+        assert!(cache.load(&filepath, 7).is_some());
+
+        fs::write(&mtl_filepath, "newmtl sphere\n").unwrap();
+        assert!(cache.load(&filepath, 7).is_none());
+
+        store(&cache, &filepath);
+        fs::write(&filepath, OBJ.replace("v 1 0 0", "v 2 0 0")).unwrap();
+        set_modified(&filepath, SystemTime::now() + Duration::from_secs(120));
+        assert!(cache.load(&filepath, 7).is_none());
+    }
+
+    #[test]
+    fn creating_a_missing_library_invalidates_entries() {
+        let (cache, filepath) = setup("missing_library");
+        let mtl_filepath = filepath.with_extension("mtl");
+        fs::remove_file(&mtl_filepath).unwrap();
+        store(&cache, &filepath);
+        assert!(cache.load(&filepath, 7).is_some());
+
+        fs::write(&mtl_filepath, "").unwrap();
+        assert!(cache.load(&filepath, 7).is_none());
+    }
+
+    #[test]
+    fn sources_changed_during_import_invalidate_entries() {
+        let (cache, filepath) = setup("changed_during_import");
+        let sources = source_stamps(&filepath).unwrap();
+
+        // Imported after the file changed, but stamped before.
+        fs::write(&filepath, OBJ.replace("v 1 0 0", "v 2 0 0")).unwrap();
+        set_modified(&filepath, SystemTime::now() + Duration::from_secs(60));
+        cache.store(&filepath, 7, &sources, &model()).unwrap();
+
+        assert!(cache.load(&filepath, 7).is_none());
+    }
+}
@@ -0,0 +1,390 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::mesh::Vertex;
+
+/// Size of the FIFO post-transform cache meshes are optimized for and
+/// analyzed with. Most GPUs have at least this many entries.
+pub const VERTEX_CACHE_SIZE: usize = 16;
+
+/// How much worse than the cache-optimized order the overdraw ordering may
+/// make the ACMR, as a factor.
+pub const OVERDRAW_THRESHOLD: f32 = 1.05;
+
+/// Post-transform vertex cache efficiency of an index buffer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexCacheStats {
+    /// Average cache miss ratio: transformed vertices per triangle, between
+    /// 0.5 for an ideal grid and 3.0 when no vertex is ever reused.
+    pub acmr: f32,
+    /// Average transform to vertex ratio: transformed vertices per referenced
+    /// vertex, 1.0 at best.
+    pub atvr: f32,
+}
+
+impl fmt::Display for VertexCacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ACMR {:.3}, ATVR {:.3}", self.acmr, self.atvr)
+    }
+}
+
+/// Simulates a FIFO cache of `cache_size` entries over `indices`.
+pub fn analyze_vertex_cache(
+    indices: &[u32],
+    vertex_count: usize,
+    cache_size: usize,
+) -> VertexCacheStats {
+    let mut cache = FifoCache::new(vertex_count, cache_size);
+    let mut referenced = vec![false; vertex_count];
+
+    let mut misses = 0;
+    for &index in indices {
+        if cache.access(index) {
+            misses += 1;
+        }
+        referenced[index as usize] = true;
+    }
+
+    let triangles = indices.len() / 3;
+    let unique = referenced.iter().filter(|&&r| r).count();
+
+    VertexCacheStats {
+        acmr: if triangles > 0 {
+            misses as f32 / triangles as f32
+        } else {
+            0.0
+        },
+        atvr: if unique > 0 {
+            misses as f32 / unique as f32
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Runs every optimization in order: welding, vertex cache, overdraw and
+/// vertex fetch. The result draws the same triangles.
+pub fn optimize_mesh(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let (vertices, indices) = weld_vertices(vertices, indices);
+    let indices = optimize_vertex_cache(&indices, vertices.len(), VERTEX_CACHE_SIZE);
+    let indices = optimize_overdraw(&vertices, &indices, VERTEX_CACHE_SIZE, OVERDRAW_THRESHOLD);
+    optimize_vertex_fetch(&vertices, &indices)
+}
+
+/// Merges vertices with identical attributes and drops unreferenced ones.
+pub fn weld_vertices(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut output_vertices: Vec<Vertex> = Vec::with_capacity(vertices.len());
+    let mut emitted: HashMap<[u32; 16], u32> = HashMap::with_capacity(vertices.len());
+
+    let output_indices = indices
+        .iter()
+        .map(|&index| {
+            let vertex = &vertices[index as usize];
+            *emitted.entry(vertex_key(vertex)).or_insert_with(|| {
+                output_vertices.push(*vertex);
+                (output_vertices.len() - 1) as u32
+            })
+        })
+        .collect();
+
+    (output_vertices, output_indices)
+}
+
+/// Reorders triangles for the post-transform vertex cache with Tipsify
+/// (Sander, Nehab and Barczak, "Fast Triangle Reordering for Vertex Locality
+/// and Reduced Overdraw", 2007).
+pub fn optimize_vertex_cache(indices: &[u32], vertex_count: usize, cache_size: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+
+    // Triangles using each vertex, as ranges into `adjacency`.
+    let mut live = vec![0u32; vertex_count];
+    for &index in &indices[..triangle_count * 3] {
+        live[index as usize] += 1;
+    }
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for v in 0..vertex_count {
+        offsets[v + 1] = offsets[v] + live[v] as usize;
+    }
+    let mut adjacency = vec![0usize; offsets[vertex_count]];
+    let mut filled = offsets.clone();
+    for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+        for &index in corners {
+            adjacency[filled[index as usize]] = triangle;
+            filled[index as usize] += 1;
+        }
+    }
+
+    let cache_size = cache_size as u32;
+    let mut cache_time = vec![0u32; vertex_count];
+    let mut time = cache_size + 1;
+    let mut emitted = vec![false; triangle_count];
+    let mut dead_end: Vec<u32> = Vec::new();
+    let mut cursor = 0;
+    let mut output = Vec::with_capacity(triangle_count * 3);
+
+    let mut fanning = skip_dead_end(&mut dead_end, &live, &mut cursor);
+    while let Some(vertex) = fanning {
+        let mut candidates = Vec::new();
+
+        for &triangle in &adjacency[offsets[vertex as usize]..offsets[vertex as usize + 1]] {
+            if emitted[triangle] {
+                continue;
+            }
+            emitted[triangle] = true;
+
+            for &index in &indices[triangle * 3..triangle * 3 + 3] {
+                output.push(index);
+                dead_end.push(index);
+                candidates.push(index);
+                live[index as usize] -= 1;
+
+                if time - cache_time[index as usize] > cache_size {
+                    cache_time[index as usize] = time;
+                    time += 1;
+                }
+            }
+        }
+
+        // Prefer the candidate that stays in the cache longest while its
+        // remaining triangles are emitted.
+        let mut best = None;
+        let mut best_priority = -1i64;
+        for &candidate in &candidates {
+            let remaining = live[candidate as usize];
+            if remaining == 0 {
+                continue;
+            }
+
+            let age = time - cache_time[candidate as usize];
+            let priority = if age + 2 * remaining <= cache_size {
+                age as i64
+            } else {
+                0
+            };
+            if priority > best_priority {
+                best_priority = priority;
+                best = Some(candidate);
+            }
+        }
+
+        fanning = best.or_else(|| skip_dead_end(&mut dead_end, &live, &mut cursor));
+    }
+
+    output
+}
+
+fn skip_dead_end(dead_end: &mut Vec<u32>, live: &[u32], cursor: &mut usize) -> Option<u32> {
+    while let Some(vertex) = dead_end.pop() {
+        if live[vertex as usize] > 0 {
+            return Some(vertex);
+        }
+    }
+
+    while *cursor < live.len() {
+        *cursor += 1;
+        if live[*cursor - 1] > 0 {
+            return Some((*cursor - 1) as u32);
+        }
+    }
+
+    None
+}
+
+/// Reorders clusters of a cache-optimized index buffer so that triangles
+/// facing away from the mesh's center, which are likely to occlude the rest,
+/// are drawn first.
+///
+/// Clusters are split where the cache order restarts, and further wherever
+/// that keeps the ACMR within `threshold` times the original, so reordering
+/// them costs little cache efficiency.
+pub fn optimize_overdraw(
+    vertices: &[Vertex],
+    indices: &[u32],
+    cache_size: usize,
+    threshold: f32,
+) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 {
+        return Vec::new();
+    }
+
+    let mut cache = FifoCache::new(vertices.len(), cache_size);
+
+    // Triangles whose three vertices all miss start a new hard cluster.
+    let mut hard_boundaries = Vec::new();
+    for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+        if cache.access_triangle(corners) == 3 || triangle == 0 {
+            hard_boundaries.push(triangle);
+        }
+    }
+    hard_boundaries.push(triangle_count);
+
+    let mut clusters = Vec::new();
+    for range in hard_boundaries.windows(2) {
+        let (start, end) = (range[0], range[1]);
+
+        cache.clear();
+        let misses: usize = (start..end)
+            .map(|t| cache.access_triangle(&indices[t * 3..t * 3 + 3]))
+            .sum();
+        let cluster_threshold = threshold * misses as f32 / (end - start) as f32;
+
+        cache.clear();
+        let mut cluster_start = start;
+        let mut cluster_misses = 0;
+        for triangle in start..end {
+            cluster_misses += cache.access_triangle(&indices[triangle * 3..triangle * 3 + 3]);
+
+            let cluster_triangles = triangle + 1 - cluster_start;
+            if cluster_misses as f32 / cluster_triangles as f32 <= cluster_threshold {
+                clusters.push(cluster_start..triangle + 1);
+                cluster_start = triangle + 1;
+                cluster_misses = 0;
+                cache.clear();
+            }
+        }
+        if cluster_start < end {
+            clusters.push(cluster_start..end);
+        }
+    }
+
+    let triangle = |t: usize| {
+        let p = |c: usize| vertices[indices[t * 3 + c] as usize].position;
+        (p(0), p(1), p(2))
+    };
+
+    let mut mesh_centroid = Vector3::zero();
+    for t in 0..triangle_count {
+        let (p0, p1, p2) = triangle(t);
+        mesh_centroid += p0 + p1 + p2;
+    }
+    mesh_centroid /= (triangle_count * 3) as f32;
+
+    let mut sort_keys: Vec<(f32, usize)> = clusters
+        .iter()
+        .enumerate()
+        .map(|(cluster, range)| {
+            // Area-weighted centroid and normal of the cluster.
+            let mut centroid = Vector3::zero();
+            let mut normal = Vector3::zero();
+            let mut area = 0.0;
+            for t in range.clone() {
+                let (p0, p1, p2) = triangle(t);
+                let cross = (p1 - p0).cross(p2 - p0);
+                let weight = cross.magnitude();
+
+                centroid += (p0 + p1 + p2) * (weight / 3.0);
+                normal += cross;
+                area += weight;
+            }
+
+            if area <= 0.0 || normal.magnitude2() <= 0.0 {
+                return (0.0, cluster);
+            }
+            let direction = centroid / area - mesh_centroid;
+            (direction.dot(normal.normalize()), cluster)
+        })
+        .collect();
+
+    // Stable, so clusters with equal keys keep their cache-friendly order.
+    sort_keys.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+    let mut output = Vec::with_capacity(triangle_count * 3);
+    for (_, cluster) in sort_keys {
+        let range = &clusters[cluster];
+        output.extend_from_slice(&indices[range.start * 3..range.end * 3]);
+    }
+    output
+}
+
+/// Reorders vertices by first use so the vertex fetch reads memory linearly,
+/// dropping unreferenced vertices. Returns new vertices and indices.
+pub fn optimize_vertex_fetch(vertices: &[Vertex], indices: &[u32]) -> (Vec<Vertex>, Vec<u32>) {
+    let mut remap = vec![u32::MAX; vertices.len()];
+    let mut output_vertices = Vec::with_capacity(vertices.len());
+
+    let output_indices = indices
+        .iter()
+        .map(|&index| {
+            if remap[index as usize] == u32::MAX {
+                remap[index as usize] = output_vertices.len() as u32;
+                output_vertices.push(vertices[index as usize]);
+            }
+            remap[index as usize]
+        })
+        .collect();
+
+    (output_vertices, output_indices)
+}
+
+/// Bit pattern of every attribute for hashing, with -0.0 folded into 0.0.
+fn vertex_key(vertex: &Vertex) -> [u32; 16] {
+    let v = vertex;
+    let values = [
+        v.position.x,
+        v.position.y,
+        v.position.z,
+        v.normal.x,
+        v.normal.y,
+        v.normal.z,
+        v.uv.x,
+        v.uv.y,
+        v.tangent.x,
+        v.tangent.y,
+        v.tangent.z,
+        v.binormal_headedness,
+        v.color.x,
+        v.color.y,
+        v.color.z,
+        v.color.w,
+    ];
+
+    let mut key = [0; 16];
+    for (k, value) in key.iter_mut().zip(values.iter()) {
+        *k = (value + 0.0).to_bits();
+    }
+    key
+}
+
+/// Exact FIFO cache model: a vertex is cached if fewer than `size` misses
+/// happened since it was last loaded.
+struct FifoCache {
+    timestamps: Vec<u32>,
+    time: u32,
+    size: u32,
+}
+
+impl FifoCache {
+    fn new(vertex_count: usize, size: usize) -> FifoCache {
+        FifoCache {
+            timestamps: vec![0; vertex_count],
+            time: size as u32 + 1,
+            size: size as u32,
+        }
+    }
+
+    /// Returns true on a miss.
+    fn access(&mut self, index: u32) -> bool {
+        let timestamp = &mut self.timestamps[index as usize];
+        if self.time - *timestamp > self.size {
+            *timestamp = self.time;
+            self.time += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the number of misses.
+    fn access_triangle(&mut self, corners: &[u32]) -> usize {
+        corners.iter().filter(|&&i| self.access(i)).count()
+    }
+
+    fn clear(&mut self) {
+        self.time += self.size + 1;
+    }
+}
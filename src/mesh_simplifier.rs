@@ -0,0 +1,625 @@
+use cgmath::prelude::*;
+use cgmath::Vector3;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::mesh::{MeshLod, Vertex};
+use crate::{optimize_vertex_cache, VERTEX_CACHE_SIZE};
+
+/// How `generate_lods` builds the levels of detail of a mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodGeneration {
+    /// Most levels generated in addition to the full mesh.
+    pub max_levels: usize,
+    /// Fraction of the previous level's triangles each level aims for.
+    pub reduction: f32,
+    /// Largest error a level may have, relative to the mesh's extent.
+    pub max_error: f32,
+}
+
+impl Default for LodGeneration {
+    fn default() -> LodGeneration {
+        LodGeneration {
+            max_levels: 4,
+            reduction: 0.5,
+            max_error: 0.05,
+        }
+    }
+}
+
+/// Weight of the planes that keep borders and UV seams in place, relative to
+/// the triangles' planes.
+const EDGE_WEIGHT: f64 = 10.0;
+
+/// Generates increasingly coarse index buffers over the same vertices.
+///
+/// Every level is simplified from the full mesh and optimized for the vertex
+/// cache. Generation stops once a level would not remove at least a tenth of
+/// the previous level's triangles within `max_error`.
+pub fn generate_lods(
+    vertices: &[Vertex],
+    indices: &[u32],
+    generation: &LodGeneration,
+) -> Vec<MeshLod> {
+    let extent = extent(vertices, indices);
+    let mut lods: Vec<MeshLod> = Vec::new();
+
+    for _ in 0..generation.max_levels {
+        let previous_count = lods.last().map_or(indices.len(), |lod| lod.indices.len());
+        let target_count = (previous_count as f32 / 3.0 * generation.reduction) as usize * 3;
+        if target_count == 0 {
+            break;
+        }
+
+        let (simplified, error) = simplify(vertices, indices, target_count, generation.max_error);
+        if simplified.is_empty() || simplified.len() as f32 > previous_count as f32 * 0.9 {
+            break;
+        }
+
+        lods.push(MeshLod {
+            indices: optimize_vertex_cache(&simplified, vertices.len(), VERTEX_CACHE_SIZE),
+            error: error * extent,
+        });
+    }
+
+    lods
+}
+
+/// Simplifies a mesh by collapsing edges in order of their quadric error
+/// (Garland and Heckbert, "Surface Simplification Using Quadric Error
+/// Metrics", 1997) until at most `target_index_count` indices are left or no
+/// collapse stays within `target_error`.
+///
+/// Only indices change: every remaining vertex keeps its attributes, so the
+/// result can share the vertex buffer of the input. Vertices on UV or normal
+/// seams and on open borders only move along the seam or border, collapses
+/// that flip triangles are rejected, and differing normals add to the error.
+///
+/// Errors are relative to the mesh's extent. Returns the new indices and the
+/// largest error of any collapse.
+pub fn simplify(
+    vertices: &[Vertex],
+    indices: &[u32],
+    target_index_count: usize,
+    target_error: f32,
+) -> (Vec<u32>, f32) {
+    let mut result: Vec<u32> = indices[..indices.len() / 3 * 3].to_vec();
+
+    let extent = extent(vertices, &result);
+    if extent <= 0.0 {
+        return (result, 0.0);
+    }
+
+    // Positions scaled to the unit cube so errors are relative to the extent.
+    let origin = vertices
+        .get(result.first().copied().unwrap_or(0) as usize)
+        .map_or(Vector3::zero(), |v| v.position);
+    let positions: Vec<Vector3<f64>> = vertices
+        .iter()
+        .map(|v| ((v.position - origin) / extent).cast().unwrap())
+        .collect();
+
+    let (position_remap, wedge) = build_wedges(vertices);
+    let kinds = classify_vertices(vertices.len(), &result, &position_remap, &wedge);
+
+    let mut quadrics = vec![Quadric::default(); vertices.len()];
+    fill_face_quadrics(&mut quadrics, &result, &positions, &position_remap);
+    fill_edge_quadrics(&mut quadrics, &result, &positions, &position_remap, &kinds);
+
+    let error_limit = (target_error as f64) * (target_error as f64);
+    let mut result_error = 0.0f64;
+
+    while result.len() > target_index_count {
+        let edges: HashSet<(u32, u32)> = result
+            .chunks_exact(3)
+            .flat_map(|t| vec![(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+            .collect();
+        let triangles_of = vertex_triangles(vertices.len(), &result);
+
+        let collapses = pick_collapses(
+            vertices,
+            &result,
+            &positions,
+            &position_remap,
+            &kinds,
+            &edges,
+            &quadrics,
+        );
+
+        let triangle_goal = (result.len() - target_index_count) / 3;
+        let mut collapsed_triangles = 0;
+        let mut locked = vec![false; vertices.len()];
+        let mut remap: Vec<u32> = (0..vertices.len() as u32).collect();
+        let mut merged = Vec::new();
+
+        for collapse in &collapses {
+            if collapse.error > error_limit {
+                break;
+            }
+
+            let (i0, i1) = (collapse.from as usize, collapse.to as usize);
+            let (r0, r1) = (position_remap[i0] as usize, position_remap[i1] as usize);
+            if locked[r0] || locked[r1] {
+                continue;
+            }
+
+            if has_triangle_flips(
+                i0,
+                i1,
+                &result,
+                &triangles_of,
+                &positions,
+                &position_remap,
+                &wedge,
+            ) {
+                continue;
+            }
+
+            if kinds[i0] == Kind::Seam {
+                // The other side of the seam moves along with it. The open
+                // edge runs the other way there.
+                let (s0, s1) = (wedge[i0], wedge[i1]);
+                if !edges.contains(&(s1, s0)) {
+                    continue;
+                }
+                remap[s0 as usize] = s1;
+            }
+            remap[i0] = i1 as u32;
+
+            locked[r0] = true;
+            locked[r1] = true;
+            merged.push((r0, r1));
+            result_error = result_error.max(collapse.error);
+
+            collapsed_triangles += if kinds[i0] == Kind::Border { 1 } else { 2 };
+            if collapsed_triangles >= triangle_goal {
+                break;
+            }
+        }
+
+        if merged.is_empty() {
+            break;
+        }
+
+        for (r0, r1) in merged {
+            let quadric = quadrics[r0];
+            quadrics[r1].add(&quadric);
+        }
+
+        result = result
+            .chunks_exact(3)
+            .map(|t| {
+                [
+                    remap[t[0] as usize],
+                    remap[t[1] as usize],
+                    remap[t[2] as usize],
+                ]
+            })
+            .filter(|t| t[0] != t[1] && t[1] != t[2] && t[2] != t[0])
+            .flat_map(|t| t.to_vec())
+            .collect();
+    }
+
+    (result, result_error.sqrt() as f32)
+}
+
+/// Largest side of the bounding box of the referenced vertices.
+fn extent(vertices: &[Vertex], indices: &[u32]) -> f32 {
+    let mut min = Vector3::from_value(f32::INFINITY);
+    let mut max = Vector3::from_value(f32::NEG_INFINITY);
+    for &index in indices {
+        let p = vertices[index as usize].position;
+        min = vec_min(min, p);
+        max = vec_max(max, p);
+    }
+
+    let size = max - min;
+    size.x.max(size.y).max(size.z).max(0.0)
+}
+
+fn vec_min(a: Vector3<f32>, b: Vector3<f32>) -> Vector3<f32> {
+    Vector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z))
+}
+
+fn vec_max(a: Vector3<f32>, b: Vector3<f32>) -> Vector3<f32> {
+    Vector3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z))
+}
+
+/// Maps every vertex to the first vertex at the same position, and links the
+/// vertices at each position in a ring.
+fn build_wedges(vertices: &[Vertex]) -> (Vec<u32>, Vec<u32>) {
+    let mut first_at: HashMap<[u32; 3], u32> = HashMap::with_capacity(vertices.len());
+    let position_remap: Vec<u32> = vertices
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let p = v.position;
+            let key = [
+                (p.x + 0.0).to_bits(),
+                (p.y + 0.0).to_bits(),
+                (p.z + 0.0).to_bits(),
+            ];
+            *first_at.entry(key).or_insert(i as u32)
+        })
+        .collect();
+
+    let mut wedge: Vec<u32> = (0..vertices.len() as u32).collect();
+    for i in 0..vertices.len() {
+        let r = position_remap[i] as usize;
+        if r != i {
+            // Insert after the first vertex of the ring.
+            wedge[i] = wedge[r];
+            wedge[r] = i as u32;
+        }
+    }
+
+    (position_remap, wedge)
+}
+
+/// How a vertex may be collapsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Interior vertex without seams, collapses onto any neighbour.
+    Manifold,
+    /// On one open border, collapses along the border.
+    Border,
+    /// On one seam between two wedges, collapses along the seam.
+    Seam,
+    /// Anything more complex, never moves.
+    Locked,
+}
+
+impl Kind {
+    fn can_collapse_onto(self, target: Kind) -> bool {
+        match self {
+            Kind::Manifold => true,
+            Kind::Border => target == Kind::Border,
+            Kind::Seam => target == Kind::Seam,
+            Kind::Locked => false,
+        }
+    }
+}
+
+fn classify_vertices(
+    vertex_count: usize,
+    indices: &[u32],
+    position_remap: &[u32],
+    wedge: &[u32],
+) -> Vec<Kind> {
+    let edges: HashSet<(u32, u32)> = indices
+        .chunks_exact(3)
+        .flat_map(|t| vec![(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+        .collect();
+
+    // The neighbour across the single open edge into and out of each vertex;
+    // the vertex itself if there is more than one.
+    let mut open_in: Vec<Option<u32>> = vec![None; vertex_count];
+    let mut open_out: Vec<Option<u32>> = vec![None; vertex_count];
+    for &(a, b) in &edges {
+        if !edges.contains(&(b, a)) {
+            open_in[b as usize] = Some(open_in[b as usize].map_or(a, |_| b));
+            open_out[a as usize] = Some(open_out[a as usize].map_or(b, |_| a));
+        }
+    }
+
+    let mut kinds = vec![Kind::Manifold; vertex_count];
+    for i in 0..vertex_count {
+        let r = position_remap[i] as usize;
+        if r != i {
+            kinds[i] = kinds[r];
+            continue;
+        }
+
+        let w = wedge[i] as usize;
+        kinds[i] = if w == i {
+            match (open_in[i], open_out[i]) {
+                (None, None) => Kind::Manifold,
+                (Some(a), Some(b)) if a as usize != i && b as usize != i => Kind::Border,
+                _ => Kind::Locked,
+            }
+        } else if wedge[w] as usize == i {
+            match (open_in[i], open_out[i], open_in[w], open_out[w]) {
+                (Some(iv), Some(ov), Some(iw), Some(ow))
+                    if iv as usize != i
+                        && ov as usize != i
+                        && iw as usize != w
+                        && ow as usize != w
+                        && position_remap[iv as usize] == position_remap[ow as usize]
+                        && position_remap[ov as usize] == position_remap[iw as usize] =>
+                {
+                    Kind::Seam
+                }
+                _ => Kind::Locked,
+            }
+        } else {
+            Kind::Locked
+        };
+    }
+
+    kinds
+}
+
+fn fill_face_quadrics(
+    quadrics: &mut [Quadric],
+    indices: &[u32],
+    positions: &[Vector3<f64>],
+    position_remap: &[u32],
+) {
+    for t in indices.chunks_exact(3) {
+        let (p0, p1, p2) = (
+            positions[t[0] as usize],
+            positions[t[1] as usize],
+            positions[t[2] as usize],
+        );
+
+        let normal = (p1 - p0).cross(p2 - p0);
+        let area = normal.magnitude();
+        if area <= 0.0 {
+            continue;
+        }
+
+        let quadric = Quadric::from_plane(normal / area, p0, area);
+        for &index in t {
+            quadrics[position_remap[index as usize] as usize].add(&quadric);
+        }
+    }
+}
+
+/// Adds planes through every open edge, perpendicular to its triangle, so
+/// collapses that pull borders and seams away from their line are expensive.
+fn fill_edge_quadrics(
+    quadrics: &mut [Quadric],
+    indices: &[u32],
+    positions: &[Vector3<f64>],
+    position_remap: &[u32],
+    kinds: &[Kind],
+) {
+    let edges: HashSet<(u32, u32)> = indices
+        .chunks_exact(3)
+        .flat_map(|t| vec![(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+        .collect();
+
+    for t in indices.chunks_exact(3) {
+        for e in 0..3 {
+            let (i0, i1, i2) = (t[e], t[(e + 1) % 3], t[(e + 2) % 3]);
+            let k0 = kinds[i0 as usize];
+            let k1 = kinds[i1 as usize];
+            if edges.contains(&(i1, i0))
+                || !(k0 == Kind::Border || k0 == Kind::Seam || k0 == Kind::Locked)
+                || !(k1 == Kind::Border || k1 == Kind::Seam || k1 == Kind::Locked)
+            {
+                continue;
+            }
+
+            let (p0, p1, p2) = (
+                positions[i0 as usize],
+                positions[i1 as usize],
+                positions[i2 as usize],
+            );
+            let edge = p1 - p0;
+            let length = edge.magnitude();
+            let normal = edge.cross((p1 - p0).cross(p2 - p0));
+            if length <= 0.0 || normal.magnitude2() <= 0.0 {
+                continue;
+            }
+
+            let quadric = Quadric::from_plane(normal.normalize(), p0, length * EDGE_WEIGHT);
+            quadrics[position_remap[i0 as usize] as usize].add(&quadric);
+            quadrics[position_remap[i1 as usize] as usize].add(&quadric);
+        }
+    }
+}
+
+/// Triangles using each vertex, as `(offsets, triangles)`.
+fn vertex_triangles(vertex_count: usize, indices: &[u32]) -> (Vec<usize>, Vec<usize>) {
+    let mut offsets = vec![0usize; vertex_count + 1];
+    for &index in indices {
+        offsets[index as usize + 1] += 1;
+    }
+    for i in 0..vertex_count {
+        offsets[i + 1] += offsets[i];
+    }
+
+    let mut triangles = vec![0usize; indices.len()];
+    let mut filled = offsets.clone();
+    for (i, &index) in indices.iter().enumerate() {
+        triangles[filled[index as usize]] = i / 3;
+        filled[index as usize] += 1;
+    }
+
+    (offsets, triangles)
+}
+
+struct Collapse {
+    from: u32,
+    to: u32,
+    error: f64,
+}
+
+fn pick_collapses(
+    vertices: &[Vertex],
+    indices: &[u32],
+    positions: &[Vector3<f64>],
+    position_remap: &[u32],
+    kinds: &[Kind],
+    edges: &HashSet<(u32, u32)>,
+    quadrics: &[Quadric],
+) -> Vec<Collapse> {
+    let error = |from: u32, to: u32| {
+        let (from, to) = (from as usize, to as usize);
+        let distance = quadrics[position_remap[from] as usize].error(positions[to]);
+
+        // Collapsing onto a vertex with a different normal changes the
+        // shading around it, scaled by how far the vertex moves.
+        let normal_deviation = 1.0 - vertices[from].normal.dot(vertices[to].normal) as f64;
+        distance + normal_deviation.max(0.0) * (positions[to] - positions[from]).magnitude2()
+    };
+
+    let mut seen: HashSet<(u32, u32)> = HashSet::new();
+    let mut collapses = Vec::new();
+
+    for t in indices.chunks_exact(3) {
+        for e in 0..3 {
+            let (i0, i1) = (t[e], t[(e + 1) % 3]);
+            if position_remap[i0 as usize] == position_remap[i1 as usize]
+                || !seen.insert((i0.min(i1), i0.max(i1)))
+            {
+                continue;
+            }
+
+            let (k0, k1) = (kinds[i0 as usize], kinds[i1 as usize]);
+
+            // Borders and seams may only collapse along their open edges.
+            let open = !edges.contains(&(i1, i0));
+            let along_edge = |k: Kind| (k != Kind::Border && k != Kind::Seam) || open;
+
+            let collapse = if k0 == k1 && k0 != Kind::Locked {
+                if !along_edge(k0) {
+                    continue;
+                }
+                let (e01, e10) = (error(i0, i1), error(i1, i0));
+                if e01 <= e10 {
+                    (i0, i1, e01)
+                } else {
+                    (i1, i0, e10)
+                }
+            } else if k0.can_collapse_onto(k1) && along_edge(k0) {
+                (i0, i1, error(i0, i1))
+            } else if k1.can_collapse_onto(k0) && along_edge(k1) {
+                (i1, i0, error(i1, i0))
+            } else {
+                continue;
+            };
+
+            // Degenerate input, e.g. NaN positions, has no meaningful error.
+            if !collapse.2.is_finite() {
+                continue;
+            }
+
+            collapses.push(Collapse {
+                from: collapse.0,
+                to: collapse.1,
+                error: collapse.2,
+            });
+        }
+    }
+
+    collapses.sort_by(|a, b| a.error.total_cmp(&b.error));
+    collapses
+}
+
+/// Whether moving the vertices at `i0`'s position to `i1`'s would turn any
+/// remaining triangle around.
+fn has_triangle_flips(
+    i0: usize,
+    i1: usize,
+    indices: &[u32],
+    (offsets, triangles): &(Vec<usize>, Vec<usize>),
+    positions: &[Vector3<f64>],
+    position_remap: &[u32],
+    wedge: &[u32],
+) -> bool {
+    let target = positions[i1];
+    let mut w = i0;
+    loop {
+        for &triangle in &triangles[offsets[w]..offsets[w + 1]] {
+            let t = &indices[triangle * 3..triangle * 3 + 3];
+            if t.iter()
+                .any(|&i| position_remap[i as usize] == position_remap[i1])
+            {
+                // Collapses with the edge.
+                continue;
+            }
+
+            let p = |i: u32| positions[i as usize];
+            let moved = |i: u32| {
+                if position_remap[i as usize] == position_remap[i0] {
+                    target
+                } else {
+                    p(i)
+                }
+            };
+
+            let before = (p(t[1]) - p(t[0])).cross(p(t[2]) - p(t[0]));
+            let after = (moved(t[1]) - moved(t[0])).cross(moved(t[2]) - moved(t[0]));
+            if before.dot(after) <= 0.0 {
+                return true;
+            }
+        }
+
+        w = wedge[w] as usize;
+        if w == i0 {
+            return false;
+        }
+    }
+}
+
+/// Sum of squared distances to a set of weighted planes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    a00: f64,
+    a11: f64,
+    a22: f64,
+    a10: f64,
+    a20: f64,
+    a21: f64,
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    c: f64,
+    weight: f64,
+}
+
+impl Quadric {
+    /// The plane with unit `normal` through `point`.
+    fn from_plane(normal: Vector3<f64>, point: Vector3<f64>, weight: f64) -> Quadric {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        let d = -normal.dot(point);
+
+        Quadric {
+            a00: a * a * weight,
+            a11: b * b * weight,
+            a22: c * c * weight,
+            a10: a * b * weight,
+            a20: a * c * weight,
+            a21: b * c * weight,
+            b0: a * d * weight,
+            b1: b * d * weight,
+            b2: c * d * weight,
+            c: d * d * weight,
+            weight,
+        }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        self.a00 += other.a00;
+        self.a11 += other.a11;
+        self.a22 += other.a22;
+        self.a10 += other.a10;
+        self.a20 += other.a20;
+        self.a21 += other.a21;
+        self.b0 += other.b0;
+        self.b1 += other.b1;
+        self.b2 += other.b2;
+        self.c += other.c;
+        self.weight += other.weight;
+    }
+
+    /// Weighted mean squared distance of `p` to the planes.
+    fn error(&self, p: Vector3<f64>) -> f64 {
+        let (x, y, z) = (p.x, p.y, p.z);
+
+        let r = self.a00 * x * x
+            + self.a11 * y * y
+            + self.a22 * z * z
+            + 2.0 * (self.a10 * x * y + self.a20 * x * z + self.a21 * y * z)
+            + 2.0 * (self.b0 * x + self.b1 * y + self.b2 * z)
+            + self.c;
+
+        if self.weight > 0.0 {
+            r.abs() / self.weight
+        } else {
+            r.abs()
+        }
+    }
+}
@@ -0,0 +1,660 @@
+use cgmath::*;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::gltf_loader::load_gltf;
+use crate::mesh::{
+    compute_orthonormal_tangents, compute_tangents, generate_normals, Mesh, MeshLod,
+    NormalGeneration, TextureData, Vertex,
+};
+use crate::mesh_cache::{source_stamps, CachedMaterial, CachedMesh, CachedModel};
+use crate::ply_loader::load_ply;
+use crate::program_cache::{fnv1a, FNV_OFFSET_BASIS};
+use crate::stl_loader::load_stl;
+use crate::{
+    generate_lods, optimize_mesh, Bounds, Error, LodGeneration, MeshCache, Result, Texture,
+};
+
+/// A node of the model's scene graph. OBJ files load as a single root node
+/// holding every mesh.
+#[derive(Debug, Clone)]
+pub struct ModelNode {
+    pub name: Option<String>,
+    /// Transform relative to the parent node.
+    pub transform: Matrix4<f32>,
+    pub children: Vec<usize>,
+    /// Indices into `Model::meshes`.
+    pub meshes: Vec<usize>,
+}
+
+/// A mesh placed in model space by the node hierarchy.
+#[derive(Debug, Clone, Copy)]
+pub struct MeshInstance {
+    pub mesh: usize,
+    pub transform: Matrix4<f32>,
+}
+
+/// Options for `Model::with_options`.
+#[derive(Debug, Clone)]
+pub struct LoadOptions {
+    /// Used for OBJ, glTF and PLY meshes without normals.
+    pub normals: NormalGeneration,
+    /// Welds and reorders vertices and indices with `optimize_mesh`.
+    pub optimize: bool,
+    /// How levels of detail are generated, or `None`, the default, for none.
+    /// Simplification takes a while for large meshes.
+    pub lods: Option<LodGeneration>,
+    /// Where imported OBJ meshes are cached, or `None` to always parse them.
+    pub mesh_cache: Option<MeshCache>,
+    /// Loads the textures materials refer to. Without them meshes are drawn
+    /// with the permutation for untextured materials.
+    pub textures: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> LoadOptions {
+        LoadOptions {
+            normals: NormalGeneration::default(),
+            optimize: true,
+            lods: None,
+            mesh_cache: Some(MeshCache::default()),
+            textures: true,
+        }
+    }
+}
+
+impl LoadOptions {
+    /// Runs `optimize_mesh` and `generate_lods` as enabled.
+    pub(crate) fn process_mesh(
+        &self,
+        vertices: Vec<Vertex>,
+        indices: Vec<u32>,
+    ) -> (Vec<Vertex>, Vec<u32>, Vec<MeshLod>) {
+        let (vertices, indices) = if self.optimize {
+            optimize_mesh(&vertices, &indices)
+        } else {
+            (vertices, indices)
+        };
+
+        let lods = match &self.lods {
+            Some(generation) => generate_lods(&vertices, &indices, generation),
+            None => Vec::new(),
+        };
+
+        (vertices, indices, lods)
+    }
+}
+
+/// A pre-authored, coarser version of a model, such as `foo_LOD1.obj` next to
+/// `foo_LOD0.obj`.
+#[derive(Debug, Clone)]
+pub struct ModelLod {
+    /// Root nodes of this level in `Model::nodes`.
+    pub roots: Vec<usize>,
+    /// Every mesh reachable from `roots`, with its accumulated transform.
+    pub instances: Vec<MeshInstance>,
+    /// Fraction of the viewport's height the model covers below which this
+    /// level is drawn.
+    pub max_coverage: f32,
+}
+
+/// How far coverage has to move past a level's threshold before switching,
+/// as a fraction of the threshold, so models near it do not flicker.
+const LOD_HYSTERESIS: f32 = 0.1;
+
+/// The authored level of detail one placement of a model was last drawn at,
+/// which `Renderer` starts from when picking the next one. Keep one for every
+/// placement, owned alongside it, and pass the same one to each frame's draw.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModelLodState {
+    level: usize,
+}
+
+impl ModelLodState {
+    /// The level last drawn, 0 being the full model.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Picks the level for `coverage` starting from the last one, and
+    /// remembers it.
+    pub(crate) fn update(&mut self, model: &Model, coverage: f32) -> usize {
+        self.level = model.select_lod(coverage, self.level);
+        self.level
+    }
+}
+
+#[derive(Default)]
+pub struct Model {
+    pub meshes: Vec<Mesh>,
+    pub textures_loaded: Vec<TextureData>,
+    pub nodes: Vec<ModelNode>,
+    pub roots: Vec<usize>,
+    /// Every mesh reachable from `roots`, with its accumulated transform.
+    pub instances: Vec<MeshInstance>,
+    /// Coarser levels following the full model, in order.
+    pub lods: Vec<ModelLod>,
+    /// Bounds of the full model, `None` while it has no meshes.
+    bounds: Option<Bounds>,
+    /// Problems that did not stop the model from loading, such as skipped
+    /// glTF primitives that are not triangles.
+    pub warnings: Vec<String>,
+    directory: String,
+}
+
+impl Model {
+    /// Loads an OBJ, glTF (`.gltf`), binary glTF (`.glb`), PLY or STL file,
+    /// picked by extension.
+    ///
+    /// For files named like `foo_LOD0.obj`, the siblings `foo_LOD1.obj`,
+    /// `foo_LOD2.obj` and so on are loaded as `lods` until one is missing.
+    /// Each level takes over below half the previous level's coverage.
+    pub fn new(model_filepath: &str) -> Result<Model> {
+        Model::with_options(model_filepath, &LoadOptions::default())
+    }
+
+    pub fn with_options(model_filepath: &str, options: &LoadOptions) -> Result<Model> {
+        let mut model = Model::default();
+
+        let filepath = Path::new(model_filepath);
+        let directory = filepath.parent().unwrap_or_else(|| Path::new(""));
+        model.directory = match directory.to_str() {
+            Some(directory) => directory.into(),
+            None => {
+                return Err(Error::io(
+                    directory,
+                    io::Error::new(io::ErrorKind::InvalidInput, "path is not valid UTF-8"),
+                ))
+            }
+        };
+
+        let siblings = lod_siblings(filepath);
+
+        // Authored levels make generating more of them pointless.
+        let options = if siblings.is_empty() {
+            options.clone()
+        } else {
+            LoadOptions {
+                lods: None,
+                ..options.clone()
+            }
+        };
+
+        model.roots = model.load_file(filepath, &options)?;
+
+        let mut max_coverage = 1.0;
+        for sibling in siblings {
+            max_coverage *= 0.5;
+            let roots = model.load_file(&sibling, &options)?;
+            model.lods.push(ModelLod {
+                roots,
+                instances: Vec::new(),
+                max_coverage,
+            });
+        }
+
+        model.update_instances();
+
+        Ok(model)
+    }
+
+    /// Loads the meshes and nodes of one file, returning its root nodes.
+    fn load_file(&mut self, filepath: &Path, options: &LoadOptions) -> Result<Vec<usize>> {
+        let extension = filepath
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+
+        let first_mesh = self.meshes.len();
+        match extension.as_deref() {
+            Some("gltf") | Some("glb") => return load_gltf(self, filepath, options),
+            Some("ply") => {
+                let (vertices, indices) = load_ply(filepath, options.normals)?;
+                let (vertices, indices, lods) = options.process_mesh(vertices, indices);
+                self.meshes
+                    .push(Mesh::with_lods(vertices, indices, lods, Vec::new()));
+            }
+            Some("stl") => {
+                let (vertices, indices) = load_stl(filepath)?;
+                let (vertices, indices, lods) = options.process_mesh(vertices, indices);
+                self.meshes
+                    .push(Mesh::with_lods(vertices, indices, lods, Vec::new()));
+            }
+            _ => self.load_model(filepath, options)?,
+        }
+
+        Ok(vec![self.add_root_node(first_mesh)])
+    }
+
+    /// Adds an untransformed root node holding every mesh from `first_mesh`
+    /// on, for formats without a scene graph.
+    fn add_root_node(&mut self, first_mesh: usize) -> usize {
+        self.nodes.push(ModelNode {
+            name: None,
+            transform: Matrix4::identity(),
+            children: Vec::new(),
+            meshes: (first_mesh..self.meshes.len()).collect(),
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Recomputes `instances` and those of every level of detail after the
+    /// node hierarchy changed.
+    pub fn update_instances(&mut self) {
+        self.instances = self.collect_root_instances(&self.roots);
+        for i in 0..self.lods.len() {
+            self.lods[i].instances = self.collect_root_instances(&self.lods[i].roots);
+        }
+
+        let mesh_bounds: Vec<Bounds> = self
+            .instances
+            .iter()
+            .map(|instance| {
+                self.meshes[instance.mesh]
+                    .bounds()
+                    .transformed(&instance.transform)
+            })
+            .collect();
+        self.bounds = Bounds::merge(&mesh_bounds);
+    }
+
+    /// Bounds of the full model in model space, placed by the node hierarchy.
+    /// Empty at the origin for models without meshes.
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+            .unwrap_or_else(|| Bounds::point(Point3::origin()))
+    }
+
+    /// Bounds of the full model once placed by `model_matrix`.
+    pub fn transformed_bounds(&self, model_matrix: &Matrix4<f32>) -> Bounds {
+        self.bounds().transformed(model_matrix)
+    }
+
+    /// Picks the level to draw at the given coverage, the fraction of the
+    /// viewport's height the model's bounding sphere spans, for an instance
+    /// that was drawn at level `previous` last frame.
+    ///
+    /// A level is only left once coverage is `LOD_HYSTERESIS` past its
+    /// threshold, so a model hovering around one does not pop back and forth.
+    pub fn select_lod(&self, coverage: f32, previous: usize) -> usize {
+        let max_coverage = |level: usize| match level {
+            0 => f32::INFINITY,
+            level => self.lods[level - 1].max_coverage,
+        };
+
+        let mut level = previous.min(self.lods.len());
+        while level < self.lods.len() && coverage < max_coverage(level + 1) * (1.0 - LOD_HYSTERESIS)
+        {
+            level += 1;
+        }
+        while level > 0 && coverage > max_coverage(level) * (1.0 + LOD_HYSTERESIS) {
+            level -= 1;
+        }
+
+        level
+    }
+
+    /// The instances to draw for `level`, 0 being the full model.
+    pub fn lod_instances(&self, level: usize) -> &[MeshInstance] {
+        match level {
+            0 => &self.instances,
+            level => &self.lods[level - 1].instances,
+        }
+    }
+
+    fn collect_root_instances(&self, roots: &[usize]) -> Vec<MeshInstance> {
+        let mut instances = Vec::new();
+        for &root in roots {
+            self.collect_instances(root, Matrix4::identity(), &mut instances);
+        }
+        instances
+    }
+
+    fn collect_instances(
+        &self,
+        node_index: usize,
+        parent_transform: Matrix4<f32>,
+        instances: &mut Vec<MeshInstance>,
+    ) {
+        let node = &self.nodes[node_index];
+        let transform = parent_transform * node.transform;
+
+        for &mesh in &node.meshes {
+            instances.push(MeshInstance { mesh, transform });
+        }
+        for &child in &node.children {
+            self.collect_instances(child, transform, instances);
+        }
+    }
+
+    /// Returns the texture loaded under `key`, loading it with `load` the first
+    /// time. Textures are shared between meshes and material slots.
+    pub(crate) fn cached_texture<F>(
+        &mut self,
+        key: &str,
+        texture_type: &str,
+        load: F,
+    ) -> Result<TextureData>
+    where
+        F: FnOnce() -> Result<Texture>,
+    {
+        let texture = match self.textures_loaded.iter().find(|t| t.filepath == key) {
+            Some(texture_data) => texture_data.texture.clone(),
+            None => {
+                let texture = Rc::new(load()?);
+                self.textures_loaded.push(TextureData {
+                    texture: texture.clone(),
+                    texture_type: String::from(texture_type),
+                    filepath: key.into(),
+                });
+                texture
+            }
+        };
+
+        Ok(TextureData {
+            texture,
+            texture_type: String::from(texture_type),
+            filepath: key.into(),
+        })
+    }
+
+    fn load_model(&mut self, filepath: &Path, options: &LoadOptions) -> Result<()> {
+        fs::metadata(filepath).map_err(|e| Error::io(filepath, e))?;
+
+        // Everything that changes the imported meshes has to be part of the key.
+        let options_key = fnv1a(
+            FNV_OFFSET_BASIS,
+            format!("{:?}", (options.normals, options.optimize, options.lods)).as_bytes(),
+        );
+
+        let cached = options
+            .mesh_cache
+            .as_ref()
+            .and_then(|cache| cache.load(filepath, options_key));
+        let imported = match cached {
+            Some(imported) => imported,
+            None => {
+                let sources = options.mesh_cache.as_ref().map(|_| source_stamps(filepath));
+                let imported = import_obj(filepath, options)?;
+                if let (Some(cache), Some(sources)) = (&options.mesh_cache, sources) {
+                    let stored =
+                        sources.and_then(|s| cache.store(filepath, options_key, &s, &imported));
+                    if let Err(e) = stored {
+                        log::warn!(
+                            "Failed to write mesh cache in {}: {}",
+                            cache.directory().display(),
+                            e
+                        );
+                    }
+                }
+                imported
+            }
+        };
+
+        for mesh in imported.meshes {
+            let mut textures = Vec::new();
+            if let Some(material) = mesh.material.filter(|_| options.textures) {
+                for (texture_type, texture_filepath) in &imported.materials[material].textures {
+                    textures.push(self.load_material_texture(texture_filepath, texture_type)?);
+                }
+            }
+
+            self.meshes.push(Mesh::with_lods(
+                mesh.vertices,
+                mesh.indices,
+                mesh.lods,
+                textures,
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn load_material_texture(
+        &mut self,
+        texture_filepath: &str,
+        texture_type: &str,
+    ) -> Result<TextureData> {
+        let filepath = format!("{}/{}", self.directory, texture_filepath);
+        self.cached_texture(texture_filepath, texture_type, || Texture::new(&filepath))
+    }
+}
+
+/// The existing `foo_LOD1.ext`, `foo_LOD2.ext`, ... following `foo_LOD0.ext`,
+/// up to the first missing one. Empty for files not named like that.
+fn lod_siblings(filepath: &Path) -> Vec<PathBuf> {
+    let stem = match filepath.file_stem().and_then(|s| s.to_str()) {
+        Some(stem) => stem,
+        None => return Vec::new(),
+    };
+    // Keeps the spelling of `_LOD`, dropping the `0`.
+    let prefix = match stem.len().checked_sub(5) {
+        Some(split)
+            if stem.is_char_boundary(split) && stem[split..].eq_ignore_ascii_case("_lod0") =>
+        {
+            &stem[..split + 4]
+        }
+        _ => return Vec::new(),
+    };
+    let extension = filepath
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+        .map(|level| filepath.with_file_name(format!("{}{}{}", prefix, level, extension)))
+        .take_while(|sibling| sibling.is_file())
+        .collect()
+}
+
+/// Imports the meshes of an OBJ file as `Model::with_options` does, with
+/// normals, tangents and optimization applied, but without creating any GL
+/// objects. Returns the full-detail vertices and indices of every mesh and
+/// bypasses the mesh cache.
+///
+/// Only public for the tangent tests, which run without a GL context; not
+/// part of the supported API.
+#[doc(hidden)]
+pub fn import_obj_meshes<P: AsRef<Path>>(
+    filepath: P,
+    options: &LoadOptions,
+) -> Result<Vec<(Vec<Vertex>, Vec<u32>)>> {
+    let imported = import_obj(filepath.as_ref(), options)?;
+
+    Ok(imported
+        .meshes
+        .into_iter()
+        .map(|mesh| (mesh.vertices, mesh.indices))
+        .collect())
+}
+
+/// Parses an OBJ file and generates whatever the meshes are missing.
+fn import_obj(filepath: &Path, options: &LoadOptions) -> Result<CachedModel> {
+    let parse_error = |source| Error::ObjParse {
+        path: filepath.into(),
+        source,
+    };
+    // One index per vertex, like the GL buffers, and failing on a missing
+    // MTL library like tobj 2 did.
+    let load_options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ..tobj::LoadOptions::default()
+    };
+    let (models, materials) = tobj::load_obj(filepath, &load_options).map_err(parse_error)?;
+    let materials = materials.map_err(parse_error)?;
+
+    let mut imported = CachedModel {
+        meshes: Vec::new(),
+        materials: materials
+            .iter()
+            .map(|material| {
+                let slots = [
+                    (&material.diffuse_texture, "uAlbedoTexture"),
+                    (&material.normal_texture, "uNormalTexture"),
+                    (&material.shininess_texture, "uRoughnessTexture"),
+                    (&material.ambient_texture, "uMetallicTexture"),
+                ];
+
+                CachedMaterial {
+                    textures: slots
+                        .iter()
+                        .filter(|(texture_filepath, _)| !texture_filepath.is_empty())
+                        .map(|(texture_filepath, texture_type)| {
+                            (texture_type.to_string(), texture_filepath.to_string())
+                        })
+                        .collect(),
+                }
+            })
+            .collect(),
+    };
+
+    for model in models {
+        let mesh = &model.mesh;
+        let num_vertices = mesh.positions.len() / 3;
+
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(num_vertices);
+        let mut indices: Vec<u32> = mesh.indices.clone();
+
+        if num_vertices == 0 || indices.is_empty() {
+            continue;
+        }
+
+        let has_normals = mesh.normals.len() == num_vertices * 3;
+        let has_uvs = mesh.texcoords.len() == num_vertices * 2;
+
+        // Load vertices
+        let (p, n, t) = (&mesh.positions, &mesh.normals, &mesh.texcoords);
+        for i in 0..num_vertices {
+            let mut vertex = Vertex {
+                position: vec3(p[i * 3], p[i * 3 + 1], p[i * 3 + 2]),
+                ..Vertex::default()
+            };
+            if has_normals {
+                vertex.normal = vec3(n[i * 3], n[i * 3 + 1], n[i * 3 + 2]);
+            }
+            if has_uvs {
+                vertex.uv = vec2(t[i * 2], 1.0 - t[i * 2 + 1]);
+            }
+            vertices.push(vertex);
+        }
+
+        if !has_normals {
+            let (generated_vertices, generated_indices) =
+                generate_normals(&vertices, &indices, options.normals);
+            vertices = generated_vertices;
+            indices = generated_indices;
+        }
+
+        if has_uvs {
+            let (tangent_vertices, tangent_indices) = compute_tangents(&vertices, &indices);
+            vertices = tangent_vertices;
+            indices = tangent_indices;
+        } else {
+            compute_orthonormal_tangents(&mut vertices);
+        }
+
+        let (vertices, indices, lods) = options.process_mesh(vertices, indices);
+
+        imported.meshes.push(CachedMesh {
+            vertices,
+            indices,
+            lods,
+            material: mesh.material_id,
+        });
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn model_with_lods(max_coverages: &[f32]) -> Model {
+        let mut model = Model::default();
+        for &max_coverage in max_coverages {
+            model.lods.push(ModelLod {
+                roots: Vec::new(),
+                instances: Vec::new(),
+                max_coverage,
+            });
+        }
+        model
+    }
+
+    #[test]
+    fn lod_selection_uses_hysteresis() {
+        let model = model_with_lods(&[0.5, 0.25]);
+
+        assert_eq!(model.select_lod(1.0, 0), 0);
+        assert_eq!(model.select_lod(0.4, 0), 1);
+        assert_eq!(model.select_lod(0.1, 0), 2);
+
+        // Just past a threshold is not enough to switch either way.
+        assert_eq!(model.select_lod(0.48, 0), 0);
+        assert_eq!(model.select_lod(0.52, 1), 1);
+        assert_eq!(model.select_lod(0.56, 1), 0);
+        assert_eq!(model.select_lod(0.26, 2), 2);
+        assert_eq!(model.select_lod(0.3, 2), 1);
+
+        // Levels the model does not have are clamped.
+        assert_eq!(model.select_lod(0.1, 7), 2);
+        assert_eq!(model_with_lods(&[]).select_lod(0.1, 3), 0);
+    }
+
+    #[test]
+    fn lod_states_are_independent() {
+        let model = model_with_lods(&[0.5, 0.25]);
+        let (mut near, mut far) = (ModelLodState::default(), ModelLodState::default());
+
+        assert_eq!(far.update(&model, 0.1), 2);
+        assert_eq!(near.update(&model, 1.0), 0);
+        // Each keeps its own level through the hysteresis band.
+        assert_eq!(near.update(&model, 0.48), 0);
+        assert_eq!(far.update(&model, 0.26), 2);
+        assert_eq!((near.level(), far.level()), (0, 2));
+    }
+
+    #[test]
+    fn lod_siblings_follow_lod0() {
+        let directory = env::temp_dir()
+            .join("tofu-model-tests")
+            .join("lod_siblings");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for name in &[
+            "rock_LOD0.obj",
+            "rock_LOD1.obj",
+            "rock_LOD2.obj",
+            "rock_LOD4.obj",
+        ] {
+            fs::write(directory.join(name), "").unwrap();
+        }
+        fs::write(directory.join("tree_lod0.ply"), "").unwrap();
+        fs::write(directory.join("tree_lod1.ply"), "").unwrap();
+        fs::write(directory.join("tree_lod1.obj"), "").unwrap();
+
+        // Stops at the first missing level.
+        assert_eq!(
+            lod_siblings(&directory.join("rock_LOD0.obj")),
+            [
+                directory.join("rock_LOD1.obj"),
+                directory.join("rock_LOD2.obj")
+            ]
+        );
+        // Keeps the spelling and the extension.
+        assert_eq!(
+            lod_siblings(&directory.join("tree_lod0.ply")),
+            [directory.join("tree_lod1.ply")]
+        );
+
+        assert!(lod_siblings(&directory.join("rock_LOD1.obj")).is_empty());
+        assert!(lod_siblings(&directory.join("rock.obj")).is_empty());
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::ops::BitOr;
+
+use crate::mesh::TextureData;
+use crate::{Result, Shader, ShaderBuilder};
+
+/// The set of optional inputs a material provides, combinable with `|`.
+///
+/// Every feature maps to a `#define` of the same name, so shaders can skip
+/// sampling textures a material does not have.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MaterialFeatures(u32);
+
+impl MaterialFeatures {
+    pub const NONE: MaterialFeatures = MaterialFeatures(0);
+    pub const HAS_ALBEDO_MAP: MaterialFeatures = MaterialFeatures(1 << 0);
+    pub const HAS_NORMAL_MAP: MaterialFeatures = MaterialFeatures(1 << 1);
+    pub const HAS_ROUGHNESS_MAP: MaterialFeatures = MaterialFeatures(1 << 2);
+    pub const HAS_METALLIC_MAP: MaterialFeatures = MaterialFeatures(1 << 3);
+    /// The albedo map has an alpha channel used for alpha testing.
+    pub const HAS_ALPHA_MASK: MaterialFeatures = MaterialFeatures(1 << 4);
+    /// glTF packed map with roughness in green and metallic in blue.
+    pub const HAS_METALLIC_ROUGHNESS_MAP: MaterialFeatures = MaterialFeatures(1 << 5);
+    pub const HAS_OCCLUSION_MAP: MaterialFeatures = MaterialFeatures(1 << 6);
+    pub const HAS_EMISSIVE_MAP: MaterialFeatures = MaterialFeatures(1 << 7);
+
+    const DEFINES: &'static [(MaterialFeatures, &'static str)] = &[
+        (MaterialFeatures::HAS_ALBEDO_MAP, "HAS_ALBEDO_MAP"),
+        (MaterialFeatures::HAS_NORMAL_MAP, "HAS_NORMAL_MAP"),
+        (MaterialFeatures::HAS_ROUGHNESS_MAP, "HAS_ROUGHNESS_MAP"),
+        (MaterialFeatures::HAS_METALLIC_MAP, "HAS_METALLIC_MAP"),
+        (MaterialFeatures::HAS_ALPHA_MASK, "HAS_ALPHA_MASK"),
+        (
+            MaterialFeatures::HAS_METALLIC_ROUGHNESS_MAP,
+            "HAS_METALLIC_ROUGHNESS_MAP",
+        ),
+        (MaterialFeatures::HAS_OCCLUSION_MAP, "HAS_OCCLUSION_MAP"),
+        (MaterialFeatures::HAS_EMISSIVE_MAP, "HAS_EMISSIVE_MAP"),
+    ];
+
+    /// Derives the feature set from the textures bound to a mesh.
+    pub fn from_textures(textures: &[TextureData]) -> MaterialFeatures {
+        let mut features = MaterialFeatures::NONE;
+
+        for texture_data in textures {
+            features = features
+                | match texture_data.texture_type.as_str() {
+                    "uAlbedoTexture" if texture_data.texture.has_alpha() => {
+                        MaterialFeatures::HAS_ALBEDO_MAP | MaterialFeatures::HAS_ALPHA_MASK
+                    }
+                    "uAlbedoTexture" => MaterialFeatures::HAS_ALBEDO_MAP,
+                    "uNormalTexture" => MaterialFeatures::HAS_NORMAL_MAP,
+                    "uRoughnessTexture" => MaterialFeatures::HAS_ROUGHNESS_MAP,
+                    "uMetallicTexture" => MaterialFeatures::HAS_METALLIC_MAP,
+                    "uMetallicRoughnessTexture" => MaterialFeatures::HAS_METALLIC_ROUGHNESS_MAP,
+                    "uOcclusionTexture" => MaterialFeatures::HAS_OCCLUSION_MAP,
+                    "uEmissiveTexture" => MaterialFeatures::HAS_EMISSIVE_MAP,
+                    _ => MaterialFeatures::NONE,
+                };
+        }
+
+        features
+    }
+
+    pub fn contains(self, other: MaterialFeatures) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn remove(&mut self, other: MaterialFeatures) {
+        self.0 &= !other.0;
+    }
+
+    /// Names of the defines enabled by this feature set.
+    pub fn defines(self) -> impl Iterator<Item = &'static str> {
+        MaterialFeatures::DEFINES
+            .iter()
+            .filter(move |(feature, _)| self.contains(*feature))
+            .map(|(_, name)| *name)
+    }
+}
+
+impl BitOr for MaterialFeatures {
+    type Output = MaterialFeatures;
+
+    fn bitor(self, other: MaterialFeatures) -> MaterialFeatures {
+        MaterialFeatures(self.0 | other.0)
+    }
+}
+
+/// Lazily compiled variants of one program, one per material feature set.
+///
+/// A variant is built from the template the first time a mesh with that
+/// feature set is drawn and reused for every later mesh with the same set.
+pub struct ShaderPermutations {
+    template: ShaderBuilder,
+    variants: HashMap<MaterialFeatures, Shader>,
+}
+
+impl ShaderPermutations {
+    pub fn new(template: ShaderBuilder) -> ShaderPermutations {
+        ShaderPermutations {
+            template,
+            variants: HashMap::new(),
+        }
+    }
+
+    pub fn get(&mut self, features: MaterialFeatures) -> Result<&Shader> {
+        if !self.variants.contains_key(&features) {
+            let mut builder = self.template.clone();
+            for name in features.defines() {
+                builder = builder.define(name, "1");
+            }
+
+            self.variants.insert(features, builder.build()?);
+        }
+
+        Ok(&self.variants[&features])
+    }
+
+    /// Number of variants compiled so far.
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variants.is_empty()
+    }
+
+    /// Hot reloads every compiled variant. Returns true if any was rebuilt,
+    /// or the first error if any failed; the others are reloaded regardless.
+    pub fn reload_if_changed(&mut self) -> Result<bool> {
+        let mut reloaded = false;
+        let mut first_error = None;
+        for shader in self.variants.values_mut() {
+            match shader.reload_if_changed() {
+                Ok(changed) => reloaded |= changed,
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                }
+            }
+        }
+
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(reloaded),
+        }
+    }
+}
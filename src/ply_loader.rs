@@ -0,0 +1,594 @@
+use cgmath::*;
+
+use std::fs;
+use std::path::Path;
+
+use crate::mesh::{
+    compute_orthonormal_tangents, compute_tangents, generate_normals, NormalGeneration, Vertex,
+};
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+#[derive(Debug, Clone)]
+enum Property {
+    Scalar {
+        name: String,
+        scalar_type: ScalarType,
+    },
+    List {
+        name: String,
+        count_type: ScalarType,
+        item_type: ScalarType,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads an ASCII or binary PLY file.
+///
+/// Uses the `vertex` element's positions, and normals, texture coordinates and
+/// colors where present, and the `face` element's index lists, triangulated as
+/// fans. Missing normals are generated as configured by `normals`.
+pub(crate) fn load_ply(
+    filepath: &Path,
+    normals: NormalGeneration,
+) -> Result<(Vec<Vertex>, Vec<u32>)> {
+    let data = fs::read(filepath).map_err(|e| Error::io(filepath, e))?;
+
+    parse_ply(&data, normals).map_err(|reason| Error::MeshParse {
+        path: filepath.into(),
+        reason,
+    })
+}
+
+fn parse_ply(
+    data: &[u8],
+    normals: NormalGeneration,
+) -> std::result::Result<(Vec<Vertex>, Vec<u32>), String> {
+    let (format, elements, body_start) = parse_header(data)?;
+    let mut reader = Reader {
+        data,
+        position: body_start,
+        format,
+    };
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut has_normals = false;
+    let mut has_uvs = false;
+
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let find = |names: &[&str]| {
+                    element.properties.iter().position(|p| match p {
+                        Property::Scalar { name, .. } => names.contains(&name.as_str()),
+                        Property::List { .. } => false,
+                    })
+                };
+                let positions = [find(&["x"]), find(&["y"]), find(&["z"])];
+                let normals = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+                let uvs = [
+                    find(&["s", "u", "texture_u", "texture_s"]),
+                    find(&["t", "v", "texture_v", "texture_t"]),
+                ];
+                let colors = [
+                    find(&["red", "r"]),
+                    find(&["green", "g"]),
+                    find(&["blue", "b"]),
+                    find(&["alpha", "a"]),
+                ];
+
+                if positions.iter().any(Option::is_none) {
+                    return Err("vertex element has no x, y and z".into());
+                }
+                has_normals = normals.iter().all(Option::is_some);
+                has_uvs = uvs.iter().all(Option::is_some);
+
+                let mut values = vec![0.0; element.properties.len()];
+                for _ in 0..element.count {
+                    for (property, value) in element.properties.iter().zip(&mut values) {
+                        *value = match property {
+                            Property::Scalar { scalar_type, .. } => reader.read(*scalar_type)?,
+                            Property::List { .. } => {
+                                reader.skip_list(property)?;
+                                0.0
+                            }
+                        };
+                    }
+
+                    let get = |index: Option<usize>| index.map_or(0.0, |i| values[i] as f32);
+
+                    let mut vertex = Vertex {
+                        position: vec3(get(positions[0]), get(positions[1]), get(positions[2])),
+                        ..Vertex::default()
+                    };
+
+                    if has_normals {
+                        vertex.normal = vec3(get(normals[0]), get(normals[1]), get(normals[2]));
+                    }
+                    if has_uvs {
+                        vertex.uv = vec2(get(uvs[0]), 1.0 - get(uvs[1]));
+                    }
+
+                    for (channel, index) in colors.iter().enumerate() {
+                        if let Some(i) = *index {
+                            let value = match &element.properties[i] {
+                                Property::Scalar { scalar_type, .. } => {
+                                    normalize_color(values[i], *scalar_type) as f32
+                                }
+                                Property::List { .. } => continue,
+                            };
+
+                            // Vertex colors are stored in sRGB, alpha is linear.
+                            vertex.color[channel] =
+                                if channel < 3 { value.powf(2.2) } else { value };
+                        }
+                    }
+
+                    vertices.push(vertex);
+                }
+            }
+            "face" => {
+                let list_index = element.properties.iter().position(|p| match p {
+                    Property::List { name, .. } => {
+                        name == "vertex_indices" || name == "vertex_index"
+                    }
+                    Property::Scalar { .. } => false,
+                });
+
+                // The counts come from the file, so the buffer grows as needed
+                // instead of being preallocated.
+                let mut face = Vec::new();
+                for _ in 0..element.count {
+                    for (i, property) in element.properties.iter().enumerate() {
+                        match property {
+                            Property::List {
+                                count_type,
+                                item_type,
+                                ..
+                            } if Some(i) == list_index => {
+                                let count = reader.read_unsigned(*count_type)? as usize;
+                                face.clear();
+                                for _ in 0..count {
+                                    face.push(reader.read_unsigned(*item_type)?);
+                                }
+
+                                for k in 1..count.saturating_sub(1) {
+                                    indices.extend_from_slice(&[face[0], face[k], face[k + 1]]);
+                                }
+                            }
+                            Property::List { .. } => reader.skip_list(property)?,
+                            Property::Scalar { scalar_type, .. } => {
+                                reader.read(*scalar_type)?;
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        match property {
+                            Property::Scalar { scalar_type, .. } => {
+                                reader.read(*scalar_type)?;
+                            }
+                            Property::List { .. } => reader.skip_list(property)?,
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(&index) = indices.iter().find(|&&i| i as usize >= vertices.len()) {
+        return Err(format!(
+            "face references vertex {} but there are only {} vertices",
+            index,
+            vertices.len()
+        ));
+    }
+    if vertices.is_empty() || indices.is_empty() {
+        return Err("no faces".into());
+    }
+
+    if !has_normals {
+        let (generated_vertices, generated_indices) =
+            generate_normals(&vertices, &indices, normals);
+        vertices = generated_vertices;
+        indices = generated_indices;
+    }
+    if has_uvs {
+        let (tangent_vertices, tangent_indices) = compute_tangents(&vertices, &indices);
+        vertices = tangent_vertices;
+        indices = tangent_indices;
+    } else {
+        compute_orthonormal_tangents(&mut vertices);
+    }
+
+    Ok((vertices, indices))
+}
+
+fn parse_header(data: &[u8]) -> std::result::Result<(Format, Vec<Element>, usize), String> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut position = 0;
+    let mut first = true;
+
+    loop {
+        let end = data[position..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or("unterminated header")?;
+        let line = String::from_utf8_lossy(&data[position..position + end]);
+        let line = line.trim();
+        position += end + 1;
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        if first {
+            if line != "ply" {
+                return Err("missing 'ply' magic".into());
+            }
+            first = false;
+            continue;
+        }
+
+        match words.as_slice() {
+            ["format", name, _version] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => return Err(format!("unknown format '{}'", name)),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| format!("invalid element count '{}'", count))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count_type, item_type, name] => elements
+                .last_mut()
+                .ok_or("property before any element")?
+                .properties
+                .push(Property::List {
+                    name: name.to_string(),
+                    count_type: scalar_type(count_type)?,
+                    item_type: scalar_type(item_type)?,
+                }),
+            ["property", scalar, name] => elements
+                .last_mut()
+                .ok_or("property before any element")?
+                .properties
+                .push(Property::Scalar {
+                    name: name.to_string(),
+                    scalar_type: scalar_type(scalar)?,
+                }),
+            ["end_header"] => break,
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            _ => return Err(format!("unexpected header line '{}'", line)),
+        }
+    }
+
+    Ok((format.ok_or("missing format line")?, elements, position))
+}
+
+fn scalar_type(name: &str) -> std::result::Result<ScalarType, String> {
+    Ok(match name {
+        "char" | "int8" => ScalarType::I8,
+        "uchar" | "uint8" => ScalarType::U8,
+        "short" | "int16" => ScalarType::I16,
+        "ushort" | "uint16" => ScalarType::U16,
+        "int" | "int32" => ScalarType::I32,
+        "uint" | "uint32" => ScalarType::U32,
+        "float" | "float32" => ScalarType::F32,
+        "double" | "float64" => ScalarType::F64,
+        _ => return Err(format!("unknown property type '{}'", name)),
+    })
+}
+
+fn is_integer(scalar_type: ScalarType) -> bool {
+    !matches!(scalar_type, ScalarType::F32 | ScalarType::F64)
+}
+
+/// The value that maps to 1.0 for integer color channels.
+fn integer_scale(scalar_type: ScalarType) -> f64 {
+    match scalar_type {
+        ScalarType::U16 | ScalarType::I16 => 65535.0,
+        _ => 255.0,
+    }
+}
+
+/// Maps integer values to `0..=1` the way color channels are stored. Floats
+/// are kept as they are.
+fn normalize_color(value: f64, scalar_type: ScalarType) -> f64 {
+    if is_integer(scalar_type) {
+        value / integer_scale(scalar_type)
+    } else {
+        value
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+    format: Format,
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, scalar_type: ScalarType) -> std::result::Result<f64, String> {
+        if self.format == Format::Ascii {
+            return self.read_ascii();
+        }
+
+        let size = match scalar_type {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        };
+
+        let bytes = self
+            .data
+            .get(self.position..self.position + size)
+            .ok_or("unexpected end of file")?;
+        self.position += size;
+
+        let mut buffer = [0u8; 8];
+        buffer[..size].copy_from_slice(bytes);
+        if self.format == Format::BinaryBigEndian {
+            buffer[..size].reverse();
+        }
+
+        Ok(match scalar_type {
+            ScalarType::I8 => buffer[0] as i8 as f64,
+            ScalarType::U8 => buffer[0] as f64,
+            ScalarType::I16 => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+            ScalarType::I32 => {
+                i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::U32 => {
+                u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::F32 => {
+                f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64
+            }
+            ScalarType::F64 => f64::from_le_bytes(buffer),
+        })
+    }
+
+    /// Reads a list count or vertex index, which has to be a whole number that
+    /// fits in a `u32`.
+    fn read_unsigned(&mut self, scalar_type: ScalarType) -> std::result::Result<u32, String> {
+        let value = self.read(scalar_type)?;
+        if value >= 0.0 && value <= u32::MAX as f64 && value.fract() == 0.0 {
+            Ok(value as u32)
+        } else {
+            Err(format!("expected a vertex index or count, got {}", value))
+        }
+    }
+
+    fn read_ascii(&mut self) -> std::result::Result<f64, String> {
+        while self
+            .data
+            .get(self.position)
+            .is_some_and(|b| b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+
+        let start = self.position;
+        while self
+            .data
+            .get(self.position)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+
+        if start == self.position {
+            return Err("unexpected end of file".into());
+        }
+
+        let token = String::from_utf8_lossy(&self.data[start..self.position]);
+        token
+            .parse()
+            .map_err(|_| format!("invalid number '{}'", token))
+    }
+
+    fn skip_list(&mut self, property: &Property) -> std::result::Result<(), String> {
+        if let Property::List {
+            count_type,
+            item_type,
+            ..
+        } = property
+        {
+            let count = self.read_unsigned(*count_type)?;
+            for _ in 0..count {
+                self.read(*item_type)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRIANGLE_HEADER: &str = "element vertex 3\n\
+        property float x\n\
+        property float y\n\
+        property float z\n\
+        element face 1\n\
+        property list uchar uint vertex_indices\n\
+        end_header\n";
+
+    fn parse(data: &[u8]) -> std::result::Result<(Vec<Vertex>, Vec<u32>), String> {
+        parse_ply(data, NormalGeneration::Flat)
+    }
+
+    fn binary_triangle(format: &str, to_bytes: fn(u32) -> [u8; 4]) -> Vec<u8> {
+        let mut data = format!("ply\nformat {} 1.0\n{}", format, TRIANGLE_HEADER).into_bytes();
+        for position in &[[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] {
+            for component in position {
+                data.extend_from_slice(&to_bytes(component.to_bits()));
+            }
+        }
+        data.push(3);
+        for index in 0..3 {
+            data.extend_from_slice(&to_bytes(index));
+        }
+        data
+    }
+
+    fn assert_triangle(vertices: &[Vertex], indices: &[u32]) {
+        assert_eq!(indices.len(), 3);
+        let positions: Vec<_> = indices
+            .iter()
+            .map(|&i| vertices[i as usize].position)
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0)
+            ]
+        );
+        for vertex in vertices {
+            assert_eq!(vertex.normal, Vector3::unit_z());
+        }
+    }
+
+    #[test]
+    fn ascii_quad_is_triangulated_as_a_fan() {
+        let data = "ply\n\
+            format ascii 1.0\n\
+            comment a unit quad\n\
+            element vertex 4\n\
+            property float x\n\
+            property float y\n\
+            property float z\n\
+            element face 1\n\
+            property list uchar int vertex_indices\n\
+            end_header\n\
+            0 0 0\n\
+            1 0 0\n\
+            1 1 0\n\
+            0 1 0\n\
+            4 0 1 2 3\n";
+
+        let (vertices, indices) = parse(data.as_bytes()).unwrap();
+
+        let positions: Vec<_> = indices
+            .iter()
+            .map(|&i| vertices[i as usize].position)
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn binary_little_and_big_endian() {
+        let (vertices, indices) =
+            parse(&binary_triangle("binary_little_endian", u32::to_le_bytes)).unwrap();
+        assert_triangle(&vertices, &indices);
+
+        let (vertices, indices) =
+            parse(&binary_triangle("binary_big_endian", u32::to_be_bytes)).unwrap();
+        assert_triangle(&vertices, &indices);
+    }
+
+    #[test]
+    fn truncated_binary_body_is_an_error() {
+        let mut data = binary_triangle("binary_little_endian", u32::to_le_bytes);
+        data.truncate(data.len() - 2);
+
+        assert_eq!(parse(&data).unwrap_err(), "unexpected end of file");
+    }
+
+    #[test]
+    fn invalid_indices_are_errors() {
+        let face = |indices: &str| {
+            let data = format!(
+                "ply\nformat ascii 1.0\n{}0 0 0\n1 0 0\n0 1 0\n{}\n",
+                TRIANGLE_HEADER, indices
+            );
+            parse(data.as_bytes()).unwrap_err()
+        };
+
+        assert!(face("3 0 -1 2").contains("expected a vertex index"));
+        assert!(face("3 0 1.5 2").contains("expected a vertex index"));
+        assert!(face("-3 0 1 2").contains("expected a vertex index"));
+        assert!(face("3 0 1 4294967296").contains("expected a vertex index"));
+        assert_eq!(
+            face("3 0 1 3"),
+            "face references vertex 3 but there are only 3 vertices"
+        );
+    }
+
+    #[test]
+    fn malformed_headers_are_errors() {
+        let header = |text: &str| parse_header(text.as_bytes()).unwrap_err();
+
+        assert_eq!(
+            header("plx\nformat ascii 1.0\nend_header\n"),
+            "missing 'ply' magic"
+        );
+        assert_eq!(header("ply\nformat ascii 1.0\n"), "unterminated header");
+        assert_eq!(header("ply\nend_header\n"), "missing format line");
+        assert_eq!(
+            header("ply\nformat utf8 1.0\nend_header\n"),
+            "unknown format 'utf8'"
+        );
+        assert_eq!(
+            header("ply\nformat ascii 1.0\nproperty float x\nend_header\n"),
+            "property before any element"
+        );
+        assert_eq!(
+            header("ply\nformat ascii 1.0\nelement vertex -1\nend_header\n"),
+            "invalid element count '-1'"
+        );
+        assert_eq!(
+            header("ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n"),
+            "unknown property type 'half'"
+        );
+    }
+}
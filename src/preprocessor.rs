@@ -0,0 +1,262 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Result};
+
+/// Resolves `#include "file"` directives and injects `#define`s into GLSL
+/// sources before they are handed to the driver.
+///
+/// Every file is included at most once per program, so headers need no
+/// include guards of their own. Including a file that is still being
+/// processed further up the include chain is reported as an error.
+#[derive(Clone, Default)]
+pub struct ShaderPreprocessor {
+    defines: Vec<(String, String)>,
+}
+
+/// A preprocessed source along with every file that went into it.
+///
+/// `#line` directives in the generated source use the index into `files` as
+/// their source string number, which is how driver error logs are mapped back
+/// to the original files.
+pub struct PreprocessedSource {
+    pub source: String,
+    pub files: Vec<PathBuf>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new() -> ShaderPreprocessor {
+        ShaderPreprocessor::default()
+    }
+
+    pub fn define(&mut self, name: &str, value: &str) -> &mut ShaderPreprocessor {
+        self.defines.retain(|(n, _)| n != name);
+        self.defines.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn defines(&self) -> &[(String, String)] {
+        &self.defines
+    }
+
+    pub fn process<P: AsRef<Path>>(&self, filepath: P) -> Result<PreprocessedSource> {
+        let mut output = PreprocessedSource {
+            source: String::new(),
+            files: Vec::new(),
+        };
+
+        self.process_file(filepath.as_ref(), &mut Vec::new(), &mut output)?;
+
+        Ok(output)
+    }
+
+    fn process_file(
+        &self,
+        filepath: &Path,
+        include_stack: &mut Vec<PathBuf>,
+        output: &mut PreprocessedSource,
+    ) -> Result<()> {
+        let code = fs::read_to_string(filepath).map_err(|e| Error::io(filepath, e))?;
+
+        let file_index = output.files.len();
+        output.files.push(filepath.into());
+        include_stack.push(normalize(filepath));
+
+        let is_root = file_index == 0;
+        if !is_root {
+            writeln!(output.source, "#line 1 {}", file_index).unwrap();
+        }
+
+        // Defines go right after `#version`, which may follow comments and
+        // blank lines, or first if there is none.
+        let mut defines_injected = !is_root;
+        if is_root && !code.lines().any(is_version_directive) {
+            self.write_defines(&mut output.source, 1, file_index);
+            defines_injected = true;
+        }
+
+        for (line_index, line) in code.lines().enumerate() {
+            let line_number = line_index + 1;
+            let directive = line.trim_start();
+
+            if directive.starts_with("#include") {
+                let include = parse_include(directive).ok_or_else(|| Error::ShaderInclude {
+                    path: filepath.into(),
+                    line: line_number,
+                    reason: format!("malformed include directive '{}'", directive),
+                })?;
+
+                let include_filepath = filepath
+                    .parent()
+                    .unwrap_or_else(|| Path::new(""))
+                    .join(include);
+                let normalized = normalize(&include_filepath);
+
+                if include_stack.contains(&normalized) {
+                    return Err(Error::ShaderInclude {
+                        path: filepath.into(),
+                        line: line_number,
+                        reason: format!("include cycle through {}", include_filepath.display()),
+                    });
+                }
+
+                if !output.files.iter().any(|f| normalize(f) == normalized) {
+                    self.process_file(&include_filepath, include_stack, output)
+                        .map_err(|error| match error {
+                            Error::Io { source, .. } => Error::ShaderInclude {
+                                path: filepath.into(),
+                                line: line_number,
+                                reason: format!("{}: {}", include_filepath.display(), source),
+                            },
+                            error => error,
+                        })?;
+                }
+
+                writeln!(output.source, "#line {} {}", line_number + 1, file_index).unwrap();
+                continue;
+            }
+
+            output.source.push_str(line);
+            output.source.push('\n');
+
+            if !defines_injected && is_version_directive(directive) {
+                self.write_defines(&mut output.source, line_number + 1, file_index);
+                defines_injected = true;
+            }
+        }
+
+        include_stack.pop();
+
+        Ok(())
+    }
+
+    fn write_defines(&self, source: &mut String, next_line: usize, file_index: usize) {
+        if self.defines.is_empty() {
+            return;
+        }
+
+        for (name, value) in &self.defines {
+            writeln!(source, "#define {} {}", name, value).unwrap();
+        }
+        writeln!(source, "#line {} {}", next_line, file_index).unwrap();
+    }
+}
+
+impl PreprocessedSource {
+    /// Rewrites `source:line` locations in a driver info log to `file:line`.
+    ///
+    /// Understands the common `0:12(3)` (Mesa), `0(12)` (NVIDIA) and `0:12:`
+    /// (AMD, Intel) formats.
+    pub fn remap_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| self.remap_log_line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Maps a 1-based line of the preprocessed source back to the file and
+    /// line it came from, following the generated `#line` directives.
+    pub fn source_location(&self, line: usize) -> Option<(&Path, usize)> {
+        let mut file_index = 0;
+        let mut file_line = 1;
+
+        for (index, text) in self.source.lines().enumerate() {
+            if let Some((next_line, next_file)) = parse_line_directive(text) {
+                file_line = next_line;
+                file_index = next_file;
+                continue;
+            }
+
+            if index + 1 == line {
+                return self.files.get(file_index).map(|f| (f.as_path(), file_line));
+            }
+            file_line += 1;
+        }
+
+        None
+    }
+
+    fn remap_log_line(&self, line: &str) -> String {
+        let bytes = line.as_bytes();
+
+        let mut start = 0;
+        while start < bytes.len() {
+            if !bytes[start].is_ascii_digit()
+                || (start > 0 && bytes[start - 1].is_ascii_alphanumeric())
+            {
+                start += 1;
+                continue;
+            }
+
+            let file_end = scan_digits(bytes, start);
+            let separator = bytes.get(file_end).copied();
+            if separator == Some(b':') || separator == Some(b'(') {
+                let line_start = file_end + 1;
+                let line_end = scan_digits(bytes, line_start);
+                let closed = separator == Some(b':') || bytes.get(line_end) == Some(&b')');
+
+                if line_end > line_start && closed {
+                    let file_index: usize = line[start..file_end].parse().unwrap_or(usize::MAX);
+                    if let Some(filepath) = self.files.get(file_index) {
+                        let location_end = if separator == Some(b'(') {
+                            line_end + 1
+                        } else {
+                            line_end
+                        };
+
+                        return format!(
+                            "{}{}:{}{}",
+                            &line[..start],
+                            filepath.display(),
+                            &line[line_start..line_end],
+                            &line[location_end..]
+                        );
+                    }
+                }
+            }
+
+            start = file_end;
+        }
+
+        line.into()
+    }
+}
+
+fn scan_digits(bytes: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < bytes.len() && bytes[end].is_ascii_digit() {
+        end += 1;
+    }
+    end
+}
+
+/// Whether `line` is a `#version` directive, allowing spaces around the `#`.
+fn is_version_directive(line: &str) -> bool {
+    line.trim_start()
+        .strip_prefix('#')
+        .is_some_and(|rest| rest.trim_start().starts_with("version"))
+}
+
+fn parse_include(directive: &str) -> Option<&str> {
+    let rest = directive["#include".len()..].trim();
+    let rest = rest.strip_prefix('"')?;
+    let end = rest.find('"')?;
+
+    if !rest[end + 1..].trim().is_empty() && !rest[end + 1..].trim().starts_with("//") {
+        return None;
+    }
+
+    Some(&rest[..end])
+}
+
+fn parse_line_directive(line: &str) -> Option<(usize, usize)> {
+    let mut parts = line.strip_prefix("#line ")?.split_whitespace();
+    let line_number = parts.next()?.parse().ok()?;
+    let file_index = parts.next()?.parse().ok()?;
+    Some((line_number, file_index))
+}
+
+fn normalize(filepath: &Path) -> PathBuf {
+    fs::canonicalize(filepath).unwrap_or_else(|_| filepath.into())
+}
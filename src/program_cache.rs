@@ -0,0 +1,154 @@
+use gl::types::*;
+
+use std::env;
+use std::ffi::{c_void, CStr};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{PreprocessedSource, ShaderStage};
+
+/// Stores linked program binaries on disk so later runs can skip compiling.
+///
+/// Entries are keyed by a hash of the preprocessed sources of every stage and
+/// the GL vendor, renderer and version strings, so editing a shader or
+/// updating the driver simply misses the cache. Binaries the driver refuses to
+/// load are deleted and the program is compiled from source instead.
+#[derive(Debug, Clone)]
+pub struct ProgramCache {
+    directory: PathBuf,
+}
+
+impl ProgramCache {
+    pub fn new<P: AsRef<Path>>(directory: P) -> ProgramCache {
+        ProgramCache {
+            directory: directory.as_ref().into(),
+        }
+    }
+
+    /// `tofu/programs` in the system's temporary directory.
+    pub fn default_directory() -> PathBuf {
+        env::temp_dir().join("tofu").join("programs")
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub(crate) unsafe fn key(stages: &[(ShaderStage, PreprocessedSource)]) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+
+        for name in [gl::VENDOR, gl::RENDERER, gl::VERSION] {
+            let string = gl::GetString(name);
+            if !string.is_null() {
+                hash = fnv1a(hash, CStr::from_ptr(string as *const _).to_bytes());
+            }
+            hash = fnv1a(hash, &[0]);
+        }
+
+        for (stage, preprocessed) in stages {
+            hash = fnv1a(hash, &[*stage as u8]);
+            hash = fnv1a(hash, preprocessed.source.as_bytes());
+            hash = fnv1a(hash, &[0]);
+        }
+
+        hash
+    }
+
+    /// Creates a program from a cached binary, if there is a usable one.
+    pub(crate) unsafe fn load(&self, key: u64) -> Option<GLuint> {
+        let filepath = self.entry_filepath(key);
+        let data = fs::read(&filepath).ok()?;
+        if data.len() <= 4 {
+            let _ = fs::remove_file(&filepath);
+            return None;
+        }
+
+        let (format, binary) = data.split_at(4);
+        let format = u32::from_le_bytes([format[0], format[1], format[2], format[3]]);
+
+        let program = gl::CreateProgram();
+        gl::ProgramBinary(
+            program,
+            format,
+            binary.as_ptr() as *const c_void,
+            binary.len() as GLsizei,
+        );
+
+        let mut success = gl::FALSE as GLint;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+        if success != gl::TRUE as GLint {
+            gl::DeleteProgram(program);
+            let _ = fs::remove_file(&filepath);
+            return None;
+        }
+
+        Some(program)
+    }
+
+    /// Writes the binary of a linked program. The program must have been
+    /// linked with `PROGRAM_BINARY_RETRIEVABLE_HINT` set.
+    pub(crate) unsafe fn store(&self, key: u64, program: GLuint) -> io::Result<()> {
+        if !binaries_supported() {
+            return Ok(());
+        }
+
+        let mut length = 0;
+        gl::GetProgramiv(program, gl::PROGRAM_BINARY_LENGTH, &mut length);
+        if length <= 0 {
+            return Ok(());
+        }
+
+        let mut binary = vec![0u8; length as usize];
+        let mut format = 0;
+        let mut written = 0;
+        gl::GetProgramBinary(
+            program,
+            length,
+            &mut written,
+            &mut format,
+            binary.as_mut_ptr() as *mut c_void,
+        );
+        binary.truncate(written as usize);
+
+        let mut data = Vec::with_capacity(4 + binary.len());
+        data.extend_from_slice(&format.to_le_bytes());
+        data.extend_from_slice(&binary);
+
+        // Written under a temporary name first so concurrent runs never see a
+        // partially written entry.
+        fs::create_dir_all(&self.directory)?;
+        let filepath = self.entry_filepath(key);
+        let temporary_filepath = filepath.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&temporary_filepath, &data)?;
+        fs::rename(&temporary_filepath, &filepath)
+    }
+
+    fn entry_filepath(&self, key: u64) -> PathBuf {
+        self.directory.join(format!("{:016x}.bin", key))
+    }
+}
+
+impl Default for ProgramCache {
+    fn default() -> ProgramCache {
+        ProgramCache::new(ProgramCache::default_directory())
+    }
+}
+
+unsafe fn binaries_supported() -> bool {
+    let mut formats = 0;
+    gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats);
+    formats > 0
+}
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// 64-bit FNV-1a, continuing from `hash`. Start from `FNV_OFFSET_BASIS`.
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
@@ -0,0 +1,249 @@
+use gl::types::*;
+
+use std::ptr;
+
+/// An active uniform. Members of uniform blocks have no location and a
+/// `block_index` pointing into `ShaderReflection::uniform_blocks`.
+#[derive(Debug, Clone)]
+pub struct UniformInfo {
+    pub name: String,
+    pub location: GLint,
+    pub gl_type: GLenum,
+    pub array_size: GLint,
+    pub block_index: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UniformBlockInfo {
+    pub name: String,
+    pub binding: GLuint,
+    pub data_size: GLint,
+}
+
+/// A sampler uniform and the texture unit it is currently bound to.
+#[derive(Debug, Clone)]
+pub struct SamplerInfo {
+    pub name: String,
+    pub location: GLint,
+    pub gl_type: GLenum,
+    pub unit: GLint,
+}
+
+#[derive(Debug, Clone)]
+pub struct AttributeInfo {
+    pub name: String,
+    pub location: GLint,
+    pub gl_type: GLenum,
+    pub array_size: GLint,
+}
+
+/// Everything a linked program consumes, as reported by the driver.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderReflection {
+    pub uniforms: Vec<UniformInfo>,
+    pub uniform_blocks: Vec<UniformBlockInfo>,
+    pub samplers: Vec<SamplerInfo>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+impl ShaderReflection {
+    /// # Safety
+    ///
+    /// `program` must be a linked program of the current GL context.
+    pub unsafe fn from_program(program: GLuint) -> ShaderReflection {
+        let mut reflection = ShaderReflection::default();
+
+        for index in 0..resource_count(program, gl::UNIFORM_BLOCK) {
+            let [binding, data_size] = resource_properties(
+                program,
+                gl::UNIFORM_BLOCK,
+                index,
+                [gl::BUFFER_BINDING, gl::BUFFER_DATA_SIZE],
+            );
+
+            reflection.uniform_blocks.push(UniformBlockInfo {
+                name: resource_name(program, gl::UNIFORM_BLOCK, index),
+                binding: binding as GLuint,
+                data_size,
+            });
+        }
+
+        for index in 0..resource_count(program, gl::UNIFORM) {
+            let [gl_type, location, array_size, block_index] = resource_properties(
+                program,
+                gl::UNIFORM,
+                index,
+                [gl::TYPE, gl::LOCATION, gl::ARRAY_SIZE, gl::BLOCK_INDEX],
+            );
+
+            let uniform = UniformInfo {
+                name: resource_name(program, gl::UNIFORM, index),
+                location,
+                gl_type: gl_type as GLenum,
+                array_size,
+                block_index: if block_index < 0 {
+                    None
+                } else {
+                    Some(block_index as usize)
+                },
+            };
+
+            if is_sampler_type(uniform.gl_type) {
+                let mut unit = 0;
+                gl::GetUniformiv(program, uniform.location, &mut unit);
+
+                reflection.samplers.push(SamplerInfo {
+                    name: uniform.name.clone(),
+                    location: uniform.location,
+                    gl_type: uniform.gl_type,
+                    unit,
+                });
+            }
+
+            reflection.uniforms.push(uniform);
+        }
+
+        for index in 0..resource_count(program, gl::PROGRAM_INPUT) {
+            let [gl_type, location, array_size] = resource_properties(
+                program,
+                gl::PROGRAM_INPUT,
+                index,
+                [gl::TYPE, gl::LOCATION, gl::ARRAY_SIZE],
+            );
+
+            // Built-ins such as gl_VertexID have no location.
+            if location < 0 {
+                continue;
+            }
+
+            reflection.attributes.push(AttributeInfo {
+                name: resource_name(program, gl::PROGRAM_INPUT, index),
+                location,
+                gl_type: gl_type as GLenum,
+                array_size,
+            });
+        }
+
+        reflection.attributes.sort_by_key(|a| a.location);
+
+        reflection
+    }
+
+    pub fn uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.iter().find(|u| u.name == name)
+    }
+
+    pub fn uniform_block(&self, name: &str) -> Option<&UniformBlockInfo> {
+        self.uniform_blocks.iter().find(|b| b.name == name)
+    }
+
+    pub fn sampler(&self, name: &str) -> Option<&SamplerInfo> {
+        self.samplers.iter().find(|s| s.name == name)
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&AttributeInfo> {
+        self.attributes.iter().find(|a| a.name == name)
+    }
+}
+
+/// Number of scalar components in a vertex attribute type, or `None` for
+/// types that are not plain float vectors.
+pub fn attribute_components(gl_type: GLenum) -> Option<i32> {
+    match gl_type {
+        gl::FLOAT => Some(1),
+        gl::FLOAT_VEC2 => Some(2),
+        gl::FLOAT_VEC3 => Some(3),
+        gl::FLOAT_VEC4 => Some(4),
+        _ => None,
+    }
+}
+
+pub fn is_sampler_type(gl_type: GLenum) -> bool {
+    matches!(
+        gl_type,
+        gl::SAMPLER_1D
+            | gl::SAMPLER_2D
+            | gl::SAMPLER_3D
+            | gl::SAMPLER_CUBE
+            | gl::SAMPLER_1D_SHADOW
+            | gl::SAMPLER_2D_SHADOW
+            | gl::SAMPLER_1D_ARRAY
+            | gl::SAMPLER_2D_ARRAY
+            | gl::SAMPLER_1D_ARRAY_SHADOW
+            | gl::SAMPLER_2D_ARRAY_SHADOW
+            | gl::SAMPLER_2D_MULTISAMPLE
+            | gl::SAMPLER_2D_MULTISAMPLE_ARRAY
+            | gl::SAMPLER_CUBE_SHADOW
+            | gl::SAMPLER_CUBE_MAP_ARRAY
+            | gl::SAMPLER_CUBE_MAP_ARRAY_SHADOW
+            | gl::SAMPLER_BUFFER
+            | gl::SAMPLER_2D_RECT
+            | gl::SAMPLER_2D_RECT_SHADOW
+            | gl::INT_SAMPLER_1D
+            | gl::INT_SAMPLER_2D
+            | gl::INT_SAMPLER_3D
+            | gl::INT_SAMPLER_CUBE
+            | gl::INT_SAMPLER_1D_ARRAY
+            | gl::INT_SAMPLER_2D_ARRAY
+            | gl::INT_SAMPLER_2D_MULTISAMPLE
+            | gl::INT_SAMPLER_2D_MULTISAMPLE_ARRAY
+            | gl::INT_SAMPLER_CUBE_MAP_ARRAY
+            | gl::INT_SAMPLER_BUFFER
+            | gl::INT_SAMPLER_2D_RECT
+            | gl::UNSIGNED_INT_SAMPLER_1D
+            | gl::UNSIGNED_INT_SAMPLER_2D
+            | gl::UNSIGNED_INT_SAMPLER_3D
+            | gl::UNSIGNED_INT_SAMPLER_CUBE
+            | gl::UNSIGNED_INT_SAMPLER_1D_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_2D_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE
+            | gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_CUBE_MAP_ARRAY
+            | gl::UNSIGNED_INT_SAMPLER_BUFFER
+            | gl::UNSIGNED_INT_SAMPLER_2D_RECT
+    )
+}
+
+unsafe fn resource_count(program: GLuint, interface: GLenum) -> GLuint {
+    let mut count = 0;
+    gl::GetProgramInterfaceiv(program, interface, gl::ACTIVE_RESOURCES, &mut count);
+    count as GLuint
+}
+
+unsafe fn resource_properties<const N: usize>(
+    program: GLuint,
+    interface: GLenum,
+    index: GLuint,
+    properties: [GLenum; N],
+) -> [GLint; N] {
+    let mut values = [0; N];
+    gl::GetProgramResourceiv(
+        program,
+        interface,
+        index,
+        N as GLsizei,
+        properties.as_ptr(),
+        N as GLsizei,
+        ptr::null_mut(),
+        values.as_mut_ptr(),
+    );
+    values
+}
+
+unsafe fn resource_name(program: GLuint, interface: GLenum, index: GLuint) -> String {
+    let [name_length] = resource_properties(program, interface, index, [gl::NAME_LENGTH]);
+
+    let mut name = vec![0u8; name_length.max(1) as usize];
+    let mut length = 0;
+    gl::GetProgramResourceName(
+        program,
+        interface,
+        index,
+        name.len() as GLsizei,
+        &mut length,
+        name.as_mut_ptr() as *mut GLchar,
+    );
+    name.truncate(length as usize);
+
+    String::from_utf8_lossy(&name).into_owned()
+}
@@ -0,0 +1,301 @@
+use cgmath::prelude::*;
+use cgmath::*;
+
+use crate::bounds::max_scale;
+use crate::mesh::Mesh;
+use crate::{
+    BoundingSphere, Camera, FrameUniforms, Frustum, MeshInstance, Model, ModelLodState,
+    ObjectUniforms, Result, Shader, ShaderPermutations, UniformBuffer, FRAME_UNIFORMS_BINDING,
+    OBJECT_UNIFORMS_BINDING,
+};
+
+const CLEAR_COLOR: [f32; 3] = [1.0 * 0.2, 0.37 * 0.2, 0.64 * 0.2];
+
+/// Largest simplification error, in pixels, a level of detail may show.
+pub const DEFAULT_LOD_THRESHOLD: f32 = 1.0;
+
+/// Meshes drawn and skipped by frustum culling since the last `begin_frame`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+/// Owns the per-frame and per-object uniform buffers shared by every shader.
+pub struct Renderer {
+    frame_uniforms: UniformBuffer<FrameUniforms>,
+    object_uniforms: UniformBuffer<ObjectUniforms>,
+    view_projection: Matrix4<f32>,
+    viewport_size: Vector2<f32>,
+    camera_position: Point3<f32>,
+    /// Pixels covered by one unit at a distance of one unit from the camera.
+    pixels_per_unit: f32,
+    lod_threshold: f32,
+    frustum: Frustum,
+    frustum_culling: bool,
+    culling_stats: CullingStats,
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::BACK);
+            //gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+        }
+
+        let mut viewport = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+
+        Renderer {
+            frame_uniforms: UniformBuffer::new(FRAME_UNIFORMS_BINDING),
+            object_uniforms: UniformBuffer::new(OBJECT_UNIFORMS_BINDING),
+            view_projection: Matrix4::identity(),
+            viewport_size: vec2(viewport[2] as f32, viewport[3] as f32),
+            camera_position: Point3::origin(),
+            pixels_per_unit: 0.0,
+            lod_threshold: DEFAULT_LOD_THRESHOLD,
+            frustum: Frustum::from_matrix(&Matrix4::identity()),
+            frustum_culling: true,
+            culling_stats: CullingStats::default(),
+        }
+    }
+
+    pub fn set_viewport(&mut self, width: i32, height: i32) {
+        unsafe {
+            gl::Viewport(0, 0, width, height);
+        }
+        self.viewport_size = vec2(width as f32, height as f32);
+    }
+
+    pub fn clear(&self) {
+        unsafe {
+            gl::ClearColor(CLEAR_COLOR[0], CLEAR_COLOR[1], CLEAR_COLOR[2], 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+    }
+
+    /// Uploads the per-frame uniform block and resets the culling stats. Call
+    /// once per frame before drawing.
+    pub fn begin_frame(&mut self, camera: &Camera, time: f32) {
+        let frame = FrameUniforms::new(
+            *camera.get_view(),
+            *camera.get_projection(),
+            camera.get_position().to_vec(),
+            time,
+            self.viewport_size,
+        );
+
+        self.view_projection = frame.view_projection;
+        self.camera_position = camera.get_position();
+        // The projection's y scale is the cotangent of half the vertical field
+        // of view.
+        self.pixels_per_unit = camera.get_projection().y.y * self.viewport_size.y * 0.5;
+        self.frustum = camera.frustum();
+        self.culling_stats = CullingStats::default();
+
+        unsafe {
+            self.frame_uniforms.update(&frame);
+            self.frame_uniforms.bind();
+            self.object_uniforms.bind();
+        }
+    }
+
+    /// Uploads the per-object uniform block for the next draw calls.
+    pub fn set_object_transform(&mut self, model_matrix: &Matrix4<f32>) {
+        let normal_matrix = Transform::inverse_transform(model_matrix)
+            .unwrap_or_else(Matrix4::identity)
+            .transpose();
+
+        unsafe {
+            self.object_uniforms.update(&ObjectUniforms {
+                model: *model_matrix,
+                normal: normal_matrix,
+                model_view_projection: self.view_projection * model_matrix,
+            });
+        }
+    }
+
+    /// Sets the largest simplification error, in pixels, a mesh's level of
+    /// detail may show. Zero always draws the full meshes.
+    pub fn set_lod_threshold(&mut self, pixels: f32) {
+        self.lod_threshold = pixels;
+    }
+
+    pub fn lod_threshold(&self) -> f32 {
+        self.lod_threshold
+    }
+
+    /// Skips meshes whose bounds are outside the camera's frustum when enabled,
+    /// which it is by default.
+    pub fn set_frustum_culling(&mut self, enabled: bool) {
+        self.frustum_culling = enabled;
+    }
+
+    pub fn frustum_culling(&self) -> bool {
+        self.frustum_culling
+    }
+
+    pub fn culling_stats(&self) -> CullingStats {
+        self.culling_stats
+    }
+
+    /// Tests `mesh` placed by `model_matrix` against the frustum, counting it
+    /// as drawn or culled.
+    fn cull(&mut self, mesh: &Mesh, model_matrix: &Matrix4<f32>) -> bool {
+        let visible = !self.frustum_culling
+            || self
+                .frustum
+                .intersects(&mesh.bounds().transformed(model_matrix));
+
+        if visible {
+            self.culling_stats.drawn += 1;
+        } else {
+            self.culling_stats.culled += 1;
+        }
+        !visible
+    }
+
+    /// Picks the coarsest level of detail of `mesh` whose error, projected at
+    /// the mesh's nearest distance to the camera, stays within the threshold.
+    pub fn select_lod(&self, mesh: &Mesh, model_matrix: &Matrix4<f32>) -> usize {
+        if mesh.lods.is_empty() || self.lod_threshold <= 0.0 {
+            return 0;
+        }
+
+        let scale = max_scale(model_matrix);
+        let sphere = mesh.bounds().sphere.transformed(model_matrix);
+        let distance = (sphere.center - self.camera_position).magnitude() - sphere.radius;
+        if distance <= 0.0 {
+            return 0;
+        }
+
+        let pixels_per_unit = self.pixels_per_unit * scale / distance;
+        mesh.lods
+            .iter()
+            .rposition(|lod| lod.error * pixels_per_unit <= self.lod_threshold)
+            .map_or(0, |i| i + 1)
+    }
+
+    /// Fraction of the viewport's height spanned by the model's bounding
+    /// sphere, used to pick between its authored levels of detail.
+    pub fn model_coverage(&self, model: &Model, model_matrix: &Matrix4<f32>) -> f32 {
+        let BoundingSphere { center, radius } = model.transformed_bounds(model_matrix).sphere;
+        let distance = (center - self.camera_position).magnitude();
+        if distance <= radius || self.viewport_size.y <= 0.0 {
+            return f32::INFINITY;
+        }
+
+        2.0 * radius * self.pixels_per_unit / distance / self.viewport_size.y
+    }
+
+    /// The instances of the model's authored level of detail for its current
+    /// coverage, starting from the level in `lod`.
+    fn model_instances<'a>(
+        &self,
+        model: &'a Model,
+        model_matrix: &Matrix4<f32>,
+        lod: &mut ModelLodState,
+    ) -> &'a [MeshInstance] {
+        if model.lods.is_empty() {
+            return &model.instances;
+        }
+
+        let level = lod.update(model, self.model_coverage(model, model_matrix));
+        model.lod_instances(level)
+    }
+
+    /// Draws every mesh instance of a model inside the frustum, placed by the
+    /// model's node hierarchy and then by `model_matrix`, each at the level of
+    /// detail `select_lod` picks. Models with authored levels first pick one of
+    /// those by `model_coverage`, starting from and updating `lod`, which
+    /// belongs to this placement of the model.
+    pub fn draw_model(
+        &mut self,
+        shader: &Shader,
+        model: &Model,
+        model_matrix: &Matrix4<f32>,
+        lod: &mut ModelLodState,
+    ) {
+        unsafe {
+            shader.use_program();
+        }
+
+        for instance in self.model_instances(model, model_matrix, lod) {
+            let transform = model_matrix * instance.transform;
+            let mesh = &model.meshes[instance.mesh];
+            if self.cull(mesh, &transform) {
+                continue;
+            }
+            let lod = self.select_lod(mesh, &transform);
+
+            self.set_object_transform(&transform);
+
+            unsafe {
+                mesh.draw_lod(shader, lod);
+            }
+        }
+    }
+
+    /// Draws a model picking a shader variant per mesh from `permutations`.
+    ///
+    /// Instances are grouped by feature set to avoid redundant program
+    /// switches.
+    pub fn draw_model_permutations(
+        &mut self,
+        permutations: &mut ShaderPermutations,
+        model: &Model,
+        model_matrix: &Matrix4<f32>,
+        lod: &mut ModelLodState,
+    ) -> Result<()> {
+        let mut instances: Vec<&MeshInstance> = Vec::new();
+        for instance in self.model_instances(model, model_matrix, lod) {
+            if !self.cull(
+                &model.meshes[instance.mesh],
+                &(model_matrix * instance.transform),
+            ) {
+                instances.push(instance);
+            }
+        }
+        instances.sort_by_key(|instance| model.meshes[instance.mesh].features);
+
+        let mut current_features = None;
+        for instance in instances {
+            let mesh = &model.meshes[instance.mesh];
+            let shader = permutations.get(mesh.features)?;
+
+            if current_features != Some(mesh.features) {
+                unsafe {
+                    shader.use_program();
+                }
+                current_features = Some(mesh.features);
+            }
+
+            let transform = model_matrix * instance.transform;
+            let lod = self.select_lod(mesh, &transform);
+
+            self.set_object_transform(&transform);
+
+            unsafe {
+                mesh.draw_lod(shader, lod);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer::new()
+    }
+}
+
+/// Slow spin around the Y axis used by the viewer, so offscreen renders at a
+/// given time match what the viewer shows.
+pub fn turntable_matrix(time: f32) -> Matrix4<f32> {
+    Matrix4::from_angle_y(Rad(time * 0.25))
+}
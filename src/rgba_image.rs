@@ -0,0 +1,65 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use stb_image::image;
+
+use crate::{Error, Result};
+
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> RgbaImage {
+        assert_eq!(pixels.len(), width as usize * height as usize * 4);
+
+        RgbaImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(filepath: P) -> Result<RgbaImage> {
+        let filepath = filepath.as_ref();
+        if let Err(e) = File::open(filepath) {
+            return Err(Error::io(filepath, e));
+        }
+
+        match image::load_with_depth(filepath, 4, false) {
+            image::LoadResult::ImageU8(image) => Ok(RgbaImage::new(
+                image.width as u32,
+                image.height as u32,
+                image.data,
+            )),
+            image::LoadResult::ImageF32(_) => Err(Error::ImageDecode {
+                path: filepath.into(),
+                reason: "floating point images are not supported".into(),
+            }),
+            image::LoadResult::Error(reason) => Err(Error::ImageDecode {
+                path: filepath.into(),
+                reason,
+            }),
+        }
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, filepath: P) -> Result<()> {
+        let filepath = filepath.as_ref();
+        let encode_error = |e: png::EncodingError| Error::ImageEncode {
+            path: filepath.into(),
+            reason: e.to_string(),
+        };
+
+        let file = File::create(filepath).map_err(|e| Error::io(filepath, e))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(encode_error)?;
+        writer.write_image_data(&self.pixels).map_err(encode_error)
+    }
+}
@@ -0,0 +1,665 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::ffi::CString;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::{Duration, Instant, SystemTime};
+
+use cgmath::prelude::*;
+use cgmath::{Matrix3, Matrix4, Vector2, Vector3, Vector4};
+
+use gl::types::*;
+
+use crate::mesh::VertexAttribute;
+use crate::{
+    attribute_components, Error, PreprocessedSource, ProgramCache, Result, ShaderPreprocessor,
+    ShaderReflection,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShaderStage {
+    Vertex,
+    TessControl,
+    TessEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
+}
+
+impl ShaderStage {
+    fn gl_type(self) -> GLenum {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::TessControl => gl::TESS_CONTROL_SHADER,
+            ShaderStage::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+            ShaderStage::Geometry => gl::GEOMETRY_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+            ShaderStage::Compute => gl::COMPUTE_SHADER,
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ShaderStage::Vertex => "vertex",
+            ShaderStage::TessControl => "tessellation control",
+            ShaderStage::TessEvaluation => "tessellation evaluation",
+            ShaderStage::Geometry => "geometry",
+            ShaderStage::Fragment => "fragment",
+            ShaderStage::Compute => "compute",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Collects the stages of a graphics program.
+///
+/// ```ignore
+/// let shader = ShaderBuilder::new()
+///     .vertex("assets/shaders/debug/normals.vs")
+///     .geometry("assets/shaders/debug/normals.gs")
+///     .fragment("assets/shaders/debug/normals.fs")
+///     .build()?;
+/// ```
+#[derive(Clone, Default)]
+pub struct ShaderBuilder {
+    sources: Vec<(ShaderStage, PathBuf)>,
+    preprocessor: ShaderPreprocessor,
+    program_cache: Option<ProgramCache>,
+}
+
+impl ShaderBuilder {
+    /// Starts an empty program that caches its binary in
+    /// `ProgramCache::default_directory()`.
+    pub fn new() -> ShaderBuilder {
+        ShaderBuilder {
+            program_cache: Some(ProgramCache::default()),
+            ..ShaderBuilder::default()
+        }
+    }
+
+    pub fn vertex<P: AsRef<Path>>(self, filepath: P) -> ShaderBuilder {
+        self.stage(ShaderStage::Vertex, filepath)
+    }
+
+    pub fn tess_control<P: AsRef<Path>>(self, filepath: P) -> ShaderBuilder {
+        self.stage(ShaderStage::TessControl, filepath)
+    }
+
+    pub fn tess_eval<P: AsRef<Path>>(self, filepath: P) -> ShaderBuilder {
+        self.stage(ShaderStage::TessEvaluation, filepath)
+    }
+
+    pub fn geometry<P: AsRef<Path>>(self, filepath: P) -> ShaderBuilder {
+        self.stage(ShaderStage::Geometry, filepath)
+    }
+
+    pub fn fragment<P: AsRef<Path>>(self, filepath: P) -> ShaderBuilder {
+        self.stage(ShaderStage::Fragment, filepath)
+    }
+
+    /// Sets the source of `stage`, replacing any previous one.
+    pub fn stage<P: AsRef<Path>>(mut self, stage: ShaderStage, filepath: P) -> ShaderBuilder {
+        self.sources.retain(|(s, _)| *s != stage);
+        self.sources.push((stage, filepath.as_ref().into()));
+        self
+    }
+
+    pub fn define(mut self, name: &str, value: &str) -> ShaderBuilder {
+        self.preprocessor.define(name, value);
+        self
+    }
+
+    /// Where linked program binaries are cached across runs, or `None` to
+    /// always compile from source.
+    pub fn program_cache(mut self, program_cache: Option<ProgramCache>) -> ShaderBuilder {
+        self.program_cache = program_cache;
+        self
+    }
+
+    pub fn build(&self) -> Result<Shader> {
+        let mut sources = self.sources.clone();
+        sources.sort_by_key(|(stage, _)| *stage);
+
+        Shader::from_sources(
+            sources,
+            self.preprocessor.clone(),
+            self.program_cache.clone(),
+        )
+    }
+}
+
+/// How often the source files of a shader are checked for modifications.
+const RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct Shader {
+    id: u32,
+    sources: Vec<(ShaderStage, PathBuf)>,
+    preprocessor: ShaderPreprocessor,
+    program_cache: Option<ProgramCache>,
+    watched_files: Vec<(PathBuf, Option<SystemTime>)>,
+    last_reload_check: Instant,
+    reflection: ShaderReflection,
+    uniform_locations: HashMap<String, GLint>,
+    missing_uniforms: RefCell<HashSet<String>>,
+    vertex_layout_valid: Cell<Option<bool>>,
+}
+
+impl Shader {
+    pub fn new(vertex_filepath: &str, fragment_filepath: &str) -> Result<Shader> {
+        ShaderBuilder::new()
+            .vertex(vertex_filepath)
+            .fragment(fragment_filepath)
+            .build()
+    }
+
+    /// Builds a program with `#define NAME VALUE` lines injected into every
+    /// stage, right after the `#version` directive.
+    pub fn with_defines(
+        vertex_filepath: &str,
+        fragment_filepath: &str,
+        defines: &[(&str, &str)],
+    ) -> Result<Shader> {
+        let mut builder = ShaderBuilder::new()
+            .vertex(vertex_filepath)
+            .fragment(fragment_filepath);
+        for (name, value) in defines {
+            builder = builder.define(name, value);
+        }
+        builder.build()
+    }
+
+    pub(crate) fn from_sources(
+        sources: Vec<(ShaderStage, PathBuf)>,
+        preprocessor: ShaderPreprocessor,
+        program_cache: Option<ProgramCache>,
+    ) -> Result<Shader> {
+        let (id, files) =
+            unsafe { build_program(&sources, &preprocessor, program_cache.as_ref())? };
+
+        let mut shader = Shader {
+            id: 0,
+            sources,
+            preprocessor,
+            program_cache,
+            watched_files: watch(files),
+            last_reload_check: Instant::now(),
+            reflection: ShaderReflection::default(),
+            uniform_locations: HashMap::new(),
+            missing_uniforms: RefCell::new(HashSet::new()),
+            vertex_layout_valid: Cell::new(None),
+        };
+        shader.set_program(id);
+
+        Ok(shader)
+    }
+
+    /// Recompiles the program if any of its source files changed on disk.
+    ///
+    /// The new program only replaces the current one if it compiles and links,
+    /// otherwise the error is returned and the previous program stays in use.
+    /// Returns `true` if the program was replaced.
+    pub fn reload_if_changed(&mut self) -> Result<bool> {
+        if self.last_reload_check.elapsed() < RELOAD_POLL_INTERVAL {
+            return Ok(false);
+        }
+        self.last_reload_check = Instant::now();
+
+        let mut changed = false;
+        for (filepath, modified) in &mut self.watched_files {
+            let current = modified_time(filepath);
+            if current != *modified {
+                *modified = current;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return Ok(false);
+        }
+
+        let (program, files) = unsafe {
+            build_program(
+                &self.sources,
+                &self.preprocessor,
+                self.program_cache.as_ref(),
+            )?
+        };
+
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+        self.set_program(program);
+        self.watched_files = watch(files);
+        Ok(true)
+    }
+
+    /// Swaps in a freshly linked program and rebuilds everything derived from it.
+    fn set_program(&mut self, program: GLuint) {
+        self.id = program;
+        self.reflection = unsafe { ShaderReflection::from_program(program) };
+
+        self.uniform_locations.clear();
+        for uniform in &self.reflection.uniforms {
+            if uniform.location < 0 {
+                continue;
+            }
+            if let Some(base_name) = uniform.name.strip_suffix("[0]") {
+                self.uniform_locations
+                    .insert(base_name.into(), uniform.location);
+            }
+            self.uniform_locations
+                .insert(uniform.name.clone(), uniform.location);
+        }
+
+        self.missing_uniforms.borrow_mut().clear();
+        self.vertex_layout_valid.set(None);
+    }
+
+    pub fn reflection(&self) -> &ShaderReflection {
+        &self.reflection
+    }
+
+    /// Checks that `layout` provides every attribute the program reads, with a
+    /// matching component count. Problems are reported the first time only.
+    pub fn validate_vertex_layout(&self, layout: &[VertexAttribute]) -> bool {
+        if let Some(valid) = self.vertex_layout_valid.get() {
+            return valid;
+        }
+
+        let mut valid = true;
+        for attribute in &self.reflection.attributes {
+            let provided = layout
+                .iter()
+                .find(|a| a.location as GLint == attribute.location);
+
+            let problem = match (provided, attribute_components(attribute.gl_type)) {
+                (None, _) => Some("is not provided by the vertex layout".to_string()),
+                (Some(provided), Some(components)) if provided.components != components => {
+                    Some(format!(
+                        "expects {} components but the vertex layout provides {}",
+                        components, provided.components
+                    ))
+                }
+                _ => None,
+            };
+
+            if let Some(problem) = problem {
+                log::warn!(
+                    "Attribute '{}' (location {}) of shader {} {}",
+                    attribute.name,
+                    attribute.location,
+                    self.describe(),
+                    problem
+                );
+                valid = false;
+            }
+        }
+
+        self.vertex_layout_valid.set(Some(valid));
+        valid
+    }
+
+    fn describe(&self) -> String {
+        self.sources
+            .iter()
+            .map(|(_, filepath)| filepath.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn has_stage(&self, stage: ShaderStage) -> bool {
+        self.sources.iter().any(|(s, _)| *s == stage)
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the program was linked on.
+    pub unsafe fn use_program(&self) {
+        gl::UseProgram(self.id);
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_bool(&self, name: &str, value: bool) {
+        gl::Uniform1i(self.get_location(name), value as GLint);
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_int(&self, name: &str, value: i32) {
+        gl::Uniform1i(self.get_location(name), value);
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_uint(&self, name: &str, value: u32) {
+        gl::Uniform1ui(self.get_location(name), value);
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_float(&self, name: &str, value: f32) {
+        gl::Uniform1f(self.get_location(name), value);
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_vec2(&self, name: &str, value: &Vector2<f32>) {
+        gl::Uniform2fv(self.get_location(name), 1, value.as_ptr());
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_vec3(&self, name: &str, value: &Vector3<f32>) {
+        gl::Uniform3fv(self.get_location(name), 1, value.as_ptr());
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_vec4(&self, name: &str, value: &Vector4<f32>) {
+        gl::Uniform4fv(self.get_location(name), 1, value.as_ptr());
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_mat3(&self, name: &str, value: &Matrix3<f32>) {
+        gl::UniformMatrix3fv(self.get_location(name), 1, gl::FALSE, value.as_ptr());
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_mat4(&self, name: &str, value: &Matrix4<f32>) {
+        gl::UniformMatrix4fv(self.get_location(name), 1, gl::FALSE, value.as_ptr());
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_int_array(&self, name: &str, values: &[i32]) {
+        gl::Uniform1iv(
+            self.get_location(name),
+            values.len() as GLsizei,
+            values.as_ptr(),
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_uint_array(&self, name: &str, values: &[u32]) {
+        gl::Uniform1uiv(
+            self.get_location(name),
+            values.len() as GLsizei,
+            values.as_ptr(),
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_float_array(&self, name: &str, values: &[f32]) {
+        gl::Uniform1fv(
+            self.get_location(name),
+            values.len() as GLsizei,
+            values.as_ptr(),
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_vec2_array(&self, name: &str, values: &[Vector2<f32>]) {
+        gl::Uniform2fv(
+            self.get_location(name),
+            values.len() as GLsizei,
+            values.as_ptr() as *const GLfloat,
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_vec3_array(&self, name: &str, values: &[Vector3<f32>]) {
+        gl::Uniform3fv(
+            self.get_location(name),
+            values.len() as GLsizei,
+            values.as_ptr() as *const GLfloat,
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_vec4_array(&self, name: &str, values: &[Vector4<f32>]) {
+        gl::Uniform4fv(
+            self.get_location(name),
+            values.len() as GLsizei,
+            values.as_ptr() as *const GLfloat,
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_mat3_array(&self, name: &str, values: &[Matrix3<f32>]) {
+        gl::UniformMatrix3fv(
+            self.get_location(name),
+            values.len() as GLsizei,
+            gl::FALSE,
+            values.as_ptr() as *const GLfloat,
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context with this program in use.
+    pub unsafe fn set_mat4_array(&self, name: &str, values: &[Matrix4<f32>]) {
+        gl::UniformMatrix4fv(
+            self.get_location(name),
+            values.len() as GLsizei,
+            gl::FALSE,
+            values.as_ptr() as *const GLfloat,
+        );
+    }
+
+    pub fn has_uniform(&self, name: &str) -> bool {
+        self.uniform_locations.contains_key(name)
+    }
+
+    /// Looks up a location queried after linking. Unknown names resolve to -1,
+    /// which GL silently ignores, and are reported once per program.
+    fn get_location(&self, name: &str) -> GLint {
+        if let Some(&location) = self.uniform_locations.get(name) {
+            return location;
+        }
+
+        if self.missing_uniforms.borrow_mut().insert(name.into()) {
+            log::warn!(
+                "Uniform '{}' does not exist in shader {}",
+                name,
+                self.describe()
+            );
+        }
+
+        -1
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
+
+/// Preprocesses, compiles and links all stages, returning the program and
+/// every file its sources were assembled from.
+unsafe fn build_program(
+    sources: &[(ShaderStage, PathBuf)],
+    preprocessor: &ShaderPreprocessor,
+    program_cache: Option<&ProgramCache>,
+) -> Result<(GLuint, Vec<PathBuf>)> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut stages = Vec::with_capacity(sources.len());
+
+    for (stage, filepath) in sources {
+        let preprocessed = preprocessor.process(filepath)?;
+        for file in &preprocessed.files {
+            if !files.contains(file) {
+                files.push(file.clone());
+            }
+        }
+
+        stages.push((*stage, preprocessed));
+    }
+
+    let cache_key = program_cache.map(|_| ProgramCache::key(&stages));
+    if let (Some(program_cache), Some(key)) = (program_cache, cache_key) {
+        if let Some(program) = program_cache.load(key) {
+            return Ok((program, files));
+        }
+    }
+
+    let mut shaders = Vec::with_capacity(stages.len());
+    let result = stages
+        .iter()
+        .try_for_each(|(stage, preprocessed)| {
+            shaders.push(compile_stage(*stage, preprocessed)?);
+            Ok(())
+        })
+        .and_then(|_| link_program(&shaders, program_cache.is_some()));
+
+    for shader in shaders {
+        gl::DeleteShader(shader);
+    }
+
+    let program = result?;
+
+    if let (Some(program_cache), Some(key)) = (program_cache, cache_key) {
+        if let Err(error) = program_cache.store(key, program) {
+            log::warn!(
+                "Failed to write program cache in {}: {}",
+                program_cache.directory().display(),
+                error
+            );
+        }
+    }
+
+    Ok((program, files))
+}
+
+fn watch(files: Vec<PathBuf>) -> Vec<(PathBuf, Option<SystemTime>)> {
+    files
+        .into_iter()
+        .map(|filepath| {
+            let modified = modified_time(&filepath);
+            (filepath, modified)
+        })
+        .collect()
+}
+
+fn modified_time(filepath: &Path) -> Option<SystemTime> {
+    fs::metadata(filepath).and_then(|m| m.modified()).ok()
+}
+
+unsafe fn compile_stage(stage: ShaderStage, preprocessed: &PreprocessedSource) -> Result<GLuint> {
+    let source = CString::new(preprocessed.source.as_bytes()).map_err(|e| {
+        Error::io(
+            &preprocessed.files[0],
+            io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+        )
+    })?;
+
+    let shader = gl::CreateShader(stage.gl_type());
+    gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+    gl::CompileShader(shader);
+
+    let mut success = gl::FALSE as GLint;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+    if success != gl::TRUE as GLint {
+        let mut log_length = 0;
+        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut log_length);
+
+        let mut info_log = vec![0u8; log_length.max(1) as usize];
+        gl::GetShaderInfoLog(
+            shader,
+            info_log.len() as GLsizei,
+            ptr::null_mut(),
+            info_log.as_mut_ptr() as *mut GLchar,
+        );
+        gl::DeleteShader(shader);
+
+        return Err(Error::ShaderCompile {
+            stage,
+            log: preprocessed.remap_log(&info_log_to_string(info_log)),
+        });
+    }
+
+    Ok(shader)
+}
+
+unsafe fn link_program(shaders: &[GLuint], retrievable: bool) -> Result<GLuint> {
+    let program = gl::CreateProgram();
+    if retrievable {
+        gl::ProgramParameteri(
+            program,
+            gl::PROGRAM_BINARY_RETRIEVABLE_HINT,
+            gl::TRUE as GLint,
+        );
+    }
+    for &shader in shaders {
+        gl::AttachShader(program, shader);
+    }
+    gl::LinkProgram(program);
+    for &shader in shaders {
+        gl::DetachShader(program, shader);
+    }
+
+    let mut success = gl::FALSE as GLint;
+    gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+    if success != gl::TRUE as GLint {
+        let mut log_length = 0;
+        gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut log_length);
+
+        let mut info_log = vec![0u8; log_length.max(1) as usize];
+        gl::GetProgramInfoLog(
+            program,
+            info_log.len() as GLsizei,
+            ptr::null_mut(),
+            info_log.as_mut_ptr() as *mut GLchar,
+        );
+        gl::DeleteProgram(program);
+
+        return Err(Error::ShaderLink {
+            log: info_log_to_string(info_log),
+        });
+    }
+
+    Ok(program)
+}
+
+fn info_log_to_string(mut info_log: Vec<u8>) -> String {
+    if let Some(end) = info_log.iter().position(|&c| c == 0) {
+        info_log.truncate(end);
+    }
+    String::from_utf8_lossy(&info_log).trim_end().into()
+}
@@ -0,0 +1,229 @@
+use cgmath::prelude::*;
+use cgmath::*;
+
+use std::fs;
+use std::path::Path;
+
+use crate::mesh::{compute_orthonormal_tangents, Vertex};
+use crate::{Error, Result};
+
+const BINARY_HEADER_SIZE: usize = 80;
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+/// Reads an ASCII or binary STL file.
+///
+/// Every facet gets its own three vertices with the facet normal, so the mesh
+/// is flat shaded. The normal is computed from the winding; the stored one is
+/// only used for degenerate facets, as many exporters write zeros there.
+pub(crate) fn load_stl(filepath: &Path) -> Result<(Vec<Vertex>, Vec<u32>)> {
+    let data = fs::read(filepath).map_err(|e| Error::io(filepath, e))?;
+
+    parse_stl(&data).map_err(|reason| Error::MeshParse {
+        path: filepath.into(),
+        reason,
+    })
+}
+
+fn parse_stl(data: &[u8]) -> std::result::Result<(Vec<Vertex>, Vec<u32>), String> {
+    // Binary files may also start with "solid", so trust the size first.
+    let facets = if is_binary(data) {
+        parse_binary(data)
+    } else {
+        parse_ascii(&String::from_utf8_lossy(data))
+    }?;
+
+    if facets.is_empty() {
+        return Err("no facets".into());
+    }
+
+    let mut vertices = Vec::with_capacity(facets.len() * 3);
+    for (stored_normal, corners) in &facets {
+        let cross = (corners[1] - corners[0]).cross(corners[2] - corners[0]);
+        let normal = if cross.magnitude2() > 0.0 {
+            cross.normalize()
+        } else if stored_normal.magnitude2() > 0.0 {
+            stored_normal.normalize()
+        } else {
+            Vector3::unit_z()
+        };
+
+        for &position in corners {
+            vertices.push(Vertex {
+                position,
+                normal,
+                ..Vertex::default()
+            });
+        }
+    }
+
+    compute_orthonormal_tangents(&mut vertices);
+    let indices = (0..vertices.len() as u32).collect();
+
+    Ok((vertices, indices))
+}
+
+type Facet = (Vector3<f32>, [Vector3<f32>; 3]);
+
+fn is_binary(data: &[u8]) -> bool {
+    if data.len() < BINARY_HEADER_SIZE + 4 {
+        return false;
+    }
+
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+    data.len() == BINARY_HEADER_SIZE + 4 + count * BINARY_TRIANGLE_SIZE
+        || !data.starts_with(b"solid")
+}
+
+fn parse_binary(data: &[u8]) -> std::result::Result<Vec<Facet>, String> {
+    let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+    let body = &data[BINARY_HEADER_SIZE + 4..];
+    if body.len() < count * BINARY_TRIANGLE_SIZE {
+        return Err(format!(
+            "header announces {} triangles but the file is truncated",
+            count
+        ));
+    }
+
+    let read_vector = |bytes: &[u8]| {
+        let f = |i: usize| f32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        vec3(f(0), f(4), f(8))
+    };
+
+    Ok(body
+        .chunks_exact(BINARY_TRIANGLE_SIZE)
+        .take(count)
+        .map(|triangle| {
+            (
+                read_vector(&triangle[0..12]),
+                [
+                    read_vector(&triangle[12..24]),
+                    read_vector(&triangle[24..36]),
+                    read_vector(&triangle[36..48]),
+                ],
+            )
+        })
+        .collect())
+}
+
+fn parse_ascii(text: &str) -> std::result::Result<Vec<Facet>, String> {
+    let mut facets = Vec::new();
+    let mut normal = Vector3::zero();
+    let mut corners: Vec<Vector3<f32>> = Vec::new();
+
+    let mut tokens = text.split_whitespace();
+    let read_vector = |tokens: &mut std::str::SplitWhitespace| {
+        let mut component = || {
+            let token = tokens.next().ok_or("unexpected end of file")?;
+            token
+                .parse::<f32>()
+                .map_err(|_| format!("invalid number '{}'", token))
+        };
+        Ok::<_, String>(vec3(component()?, component()?, component()?))
+    };
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "normal" => normal = read_vector(&mut tokens)?,
+            "vertex" => corners.push(read_vector(&mut tokens)?),
+            "endfacet" => {
+                // Polygons with more than three corners are triangulated as fans.
+                for k in 1..corners.len().saturating_sub(1) {
+                    facets.push((normal, [corners[0], corners[k], corners[k + 1]]));
+                }
+                corners.clear();
+                normal = Vector3::zero();
+            }
+            _ => {}
+        }
+    }
+
+    Ok(facets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary(count: u32, triangles: &[[[f32; 3]; 4]]) -> Vec<u8> {
+        let mut data = vec![0; BINARY_HEADER_SIZE];
+        data.extend_from_slice(&count.to_le_bytes());
+        for triangle in triangles {
+            for component in triangle.iter().flatten() {
+                data.extend_from_slice(&component.to_le_bytes());
+            }
+            data.extend_from_slice(&[0, 0]);
+        }
+        data
+    }
+
+    fn assert_triangle(vertices: &[Vertex], indices: &[u32]) {
+        assert_eq!(indices, [0, 1, 2]);
+        let positions: Vec<_> = vertices.iter().map(|v| v.position).collect();
+        assert_eq!(
+            positions,
+            [
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0)
+            ]
+        );
+        for vertex in vertices {
+            assert_eq!(vertex.normal, Vector3::unit_z());
+        }
+    }
+
+    #[test]
+    fn ascii_normals_follow_the_winding() {
+        let data = "solid triangle\n\
+            facet normal 0 0 0\n\
+            outer loop\n\
+            vertex 0 0 0\n\
+            vertex 1 0 0\n\
+            vertex 0 1 0\n\
+            endloop\n\
+            endfacet\n\
+            endsolid triangle\n";
+
+        let (vertices, indices) = parse_stl(data.as_bytes()).unwrap();
+        assert_triangle(&vertices, &indices);
+    }
+
+    #[test]
+    fn binary_starting_with_solid() {
+        let mut data = binary(
+            1,
+            &[[
+                [0.0, 0.0, -1.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ]],
+        );
+        data[..5].copy_from_slice(b"solid");
+
+        let (vertices, indices) = parse_stl(&data).unwrap();
+        assert_triangle(&vertices, &indices);
+    }
+
+    #[test]
+    fn truncated_binary_is_an_error() {
+        let data = binary(2, &[[[0.0; 3]; 4]]);
+
+        assert_eq!(
+            parse_stl(&data).unwrap_err(),
+            "header announces 2 triangles but the file is truncated"
+        );
+    }
+
+    #[test]
+    fn invalid_ascii_is_an_error() {
+        assert_eq!(
+            parse_stl(b"solid empty\nendsolid empty\n").unwrap_err(),
+            "no facets"
+        );
+        assert_eq!(
+            parse_stl(b"solid broken\nfacet normal 0 0 1\nouter loop\nvertex 0 x 0\n").unwrap_err(),
+            "invalid number 'x'"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use cgmath::{Matrix4, Vector2, Vector4};
+
+use gl::types::*;
+
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::mem;
+
+/// Marks plain-old-data types whose `#[repr(C)]` layout matches the GLSL
+/// std430 layout of the buffer they are stored in.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]` with the std430 layout, and every bit
+/// pattern must be a valid value, since buffers are read back from the GPU
+/// byte for byte.
+pub unsafe trait Std430: Copy {}
+
+unsafe impl Std430 for f32 {}
+unsafe impl Std430 for i32 {}
+unsafe impl Std430 for u32 {}
+unsafe impl Std430 for Vector2<f32> {}
+unsafe impl Std430 for Vector4<f32> {}
+unsafe impl Std430 for Matrix4<f32> {}
+
+/// A shader storage buffer holding a fixed number of `T`s.
+pub struct StorageBuffer<T: Std430> {
+    id: GLuint,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Std430> StorageBuffer<T> {
+    pub fn new(data: &[T]) -> StorageBuffer<T> {
+        let buffer = StorageBuffer::allocate(data.len());
+        unsafe {
+            buffer.write(0, data);
+        }
+        buffer
+    }
+
+    /// Creates a buffer of `len` elements with all bytes set to zero.
+    pub fn zeroed(len: usize) -> StorageBuffer<T> {
+        let buffer = StorageBuffer::allocate(len);
+        unsafe {
+            gl::ClearNamedBufferData(
+                buffer.id,
+                gl::R8UI,
+                gl::RED_INTEGER,
+                gl::UNSIGNED_BYTE,
+                [0u8].as_ptr() as *const c_void,
+            );
+        }
+        buffer
+    }
+
+    fn allocate(len: usize) -> StorageBuffer<T> {
+        let mut buffer = StorageBuffer {
+            id: 0,
+            len,
+            _marker: PhantomData,
+        };
+
+        unsafe {
+            gl::CreateBuffers(1, &mut buffer.id);
+            gl::NamedBufferStorage(
+                buffer.id,
+                (len.max(1) * mem::size_of::<T>()) as GLsizeiptr,
+                std::ptr::null(),
+                gl::DYNAMIC_STORAGE_BIT,
+            );
+        }
+
+        buffer
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Overwrites the elements starting at `offset` with `data`.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the buffer was created on.
+    pub unsafe fn write(&self, offset: usize, data: &[T]) {
+        assert!(offset + data.len() <= self.len, "write out of bounds");
+        if data.is_empty() {
+            return;
+        }
+
+        gl::NamedBufferSubData(
+            self.id,
+            (offset * mem::size_of::<T>()) as GLintptr,
+            mem::size_of_val(data) as GLsizeiptr,
+            data.as_ptr() as *const c_void,
+        );
+    }
+
+    /// Reads the whole buffer back. Insert a `MemoryBarrier::BUFFER_UPDATE`
+    /// barrier after the dispatch that wrote it.
+    ///
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the buffer was created on.
+    pub unsafe fn read(&self) -> Vec<T> {
+        let mut data = Vec::with_capacity(self.len);
+
+        gl::GetNamedBufferSubData(
+            self.id,
+            0,
+            (self.len * mem::size_of::<T>()) as GLsizeiptr,
+            data.as_mut_ptr() as *mut c_void,
+        );
+        data.set_len(self.len);
+
+        data
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the buffer was created on.
+    pub unsafe fn bind(&self, binding: u32) {
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, self.id);
+    }
+}
+
+impl<T: Std430> Drop for StorageBuffer<T> {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
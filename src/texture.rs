@@ -0,0 +1,170 @@
+extern crate stb_image;
+
+use stb_image::*;
+
+use gl::types::*;
+
+use std::convert::TryFrom;
+use std::ffi::c_void;
+use std::fs;
+
+use crate::{Error, Result};
+
+/// Owns a GL texture, which is deleted when dropped.
+pub struct Texture {
+    id: GLuint,
+    channels: u32,
+}
+
+impl Texture {
+    pub fn new(image_filepath: &str) -> Result<Texture> {
+        let image_bytes = fs::read(image_filepath).map_err(|e| Error::io(image_filepath, e))?;
+
+        let texture_data = match image::load_from_memory(&image_bytes) {
+            image::LoadResult::ImageU8(texture_data) => texture_data,
+            image::LoadResult::ImageF32(_) => {
+                return Err(Error::ImageDecode {
+                    path: image_filepath.into(),
+                    reason: "floating point images are not supported".into(),
+                })
+            }
+            image::LoadResult::Error(reason) => {
+                return Err(Error::ImageDecode {
+                    path: image_filepath.into(),
+                    reason,
+                })
+            }
+        };
+
+        Texture::from_pixels(
+            texture_data.width as u32,
+            texture_data.height as u32,
+            texture_data.depth as u32,
+            &texture_data.data,
+        )
+        .map_err(|error| error.in_image(image_filepath))
+    }
+
+    /// Creates a mipmapped, repeating texture from tightly packed 8-bit
+    /// pixels with 1 to 4 channels.
+    ///
+    /// Fails with `Error::InvalidPixels` if `pixels` does not hold exactly
+    /// `width * height * channels` bytes.
+    pub fn from_pixels(width: u32, height: u32, channels: u32, pixels: &[u8]) -> Result<Texture> {
+        if !(1..=4).contains(&channels) {
+            return Err(Error::InvalidPixels {
+                reason: format!("{} channels, expected 1 to 4", channels),
+            });
+        }
+        let expected_len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|len| len.checked_mul(channels as usize));
+        if expected_len != Some(pixels.len())
+            || GLsizei::try_from(width).is_err()
+            || GLsizei::try_from(height).is_err()
+        {
+            return Err(Error::InvalidPixels {
+                reason: format!(
+                    "{} bytes for a {}x{} image with {} channels",
+                    pixels.len(),
+                    width,
+                    height,
+                    channels
+                ),
+            });
+        }
+
+        let mut texture = Texture { id: 0, channels };
+
+        unsafe {
+            gl::GenTextures(1, &mut texture.id);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, texture.id);
+
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as GLint,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+
+            let source_format = match channels {
+                1 => gl::RED,
+                2 => gl::RG,
+                3 => gl::RGB,
+                _ => gl::RGBA,
+            };
+
+            // Rows of 1 and 3 channel images are not 4-byte aligned.
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA as GLint,
+                width as GLsizei,
+                height as GLsizei,
+                0,
+                source_format,
+                gl::UNSIGNED_BYTE,
+                pixels.as_ptr() as *const c_void,
+            );
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+        }
+
+        Ok(texture)
+    }
+
+    /// Creates an uninitialized single-level texture with immutable storage,
+    /// e.g. as the target of a compute shader's `imageStore`.
+    pub fn with_storage(width: u32, height: u32, internal_format: GLenum) -> Texture {
+        let mut texture = Texture { id: 0, channels: 4 };
+
+        unsafe {
+            gl::CreateTextures(gl::TEXTURE_2D, 1, &mut texture.id);
+            gl::TextureStorage2D(
+                texture.id,
+                1,
+                internal_format,
+                width as GLsizei,
+                height as GLsizei,
+            );
+            gl::TextureParameteri(texture.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TextureParameteri(texture.id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+        }
+
+        texture
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Number of channels in the source image.
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// Grayscale-alpha and RGBA source images carry an alpha channel.
+    pub fn has_alpha(&self) -> bool {
+        self.channels == 2 || self.channels == 4
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the texture was created on.
+    pub unsafe fn bind(&self, slot: u32) {
+        gl::ActiveTexture(slot);
+        gl::BindTexture(gl::TEXTURE_2D, self.id);
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
@@ -1,16 +0,0 @@
-mod app;
-pub use app::*;
-
-mod shader;
-pub use shader::*;
-
-mod texture;
-pub use texture::*;
-
-mod camera;
-pub use camera::*;
-
-mod model;
-pub use model::*;
-
-mod mesh;
@@ -0,0 +1,128 @@
+use cgmath::{Matrix4, Vector2, Vector3};
+
+use gl::types::*;
+
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+
+/// Binding point of the `PerFrame` block in `assets/shaders/common/uniforms.glsl`.
+pub const FRAME_UNIFORMS_BINDING: u32 = 0;
+/// Binding point of the `PerObject` block in `assets/shaders/common/uniforms.glsl`.
+pub const OBJECT_UNIFORMS_BINDING: u32 = 1;
+
+/// Marks types whose `#[repr(C)]` layout matches the GLSL std140 layout of
+/// the uniform block they are uploaded to.
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(C)]`, align every `vec3`/`vec4`/matrix member
+/// to 16 bytes and pad the struct to a multiple of 16 bytes, since values are
+/// copied into the buffer byte for byte.
+pub unsafe trait Std140: Copy {}
+
+/// A uniform buffer holding a single `T`, bound to a fixed binding point.
+pub struct UniformBuffer<T: Std140> {
+    id: GLuint,
+    binding: u32,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Std140> UniformBuffer<T> {
+    pub fn new(binding: u32) -> UniformBuffer<T> {
+        let mut buffer = UniformBuffer {
+            id: 0,
+            binding,
+            _marker: PhantomData,
+        };
+
+        unsafe {
+            gl::CreateBuffers(1, &mut buffer.id);
+            gl::NamedBufferStorage(
+                buffer.id,
+                mem::size_of::<T>() as GLsizeiptr,
+                ptr::null(),
+                gl::DYNAMIC_STORAGE_BIT,
+            );
+        }
+
+        unsafe {
+            buffer.bind();
+        }
+        buffer
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the buffer was created on.
+    pub unsafe fn update(&self, value: &T) {
+        gl::NamedBufferSubData(
+            self.id,
+            0,
+            mem::size_of::<T>() as GLsizeiptr,
+            value as *const T as *const c_void,
+        );
+    }
+
+    /// # Safety
+    ///
+    /// Requires a current GL context, the one the buffer was created on.
+    pub unsafe fn bind(&self) {
+        gl::BindBufferBase(gl::UNIFORM_BUFFER, self.binding, self.id);
+    }
+}
+
+impl<T: Std140> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
+
+/// Contents of the `PerFrame` uniform block.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FrameUniforms {
+    pub view: Matrix4<f32>,
+    pub projection: Matrix4<f32>,
+    pub view_projection: Matrix4<f32>,
+    pub camera_position: Vector3<f32>,
+    pub time: f32,
+    pub resolution: Vector2<f32>,
+    _padding: Vector2<f32>,
+}
+
+impl FrameUniforms {
+    pub fn new(
+        view: Matrix4<f32>,
+        projection: Matrix4<f32>,
+        camera_position: Vector3<f32>,
+        time: f32,
+        resolution: Vector2<f32>,
+    ) -> FrameUniforms {
+        FrameUniforms {
+            view,
+            projection,
+            view_projection: projection * view,
+            camera_position,
+            time,
+            resolution,
+            _padding: Vector2::new(0.0, 0.0),
+        }
+    }
+}
+
+unsafe impl Std140 for FrameUniforms {}
+
+/// Contents of the `PerObject` uniform block.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ObjectUniforms {
+    pub model: Matrix4<f32>,
+    pub normal: Matrix4<f32>,
+    pub model_view_projection: Matrix4<f32>,
+}
+
+unsafe impl Std140 for ObjectUniforms {}
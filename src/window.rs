@@ -0,0 +1,88 @@
+extern crate glfw;
+use self::glfw::Context;
+
+extern crate gl;
+
+use std::sync::mpsc::Receiver;
+
+use crate::{Error, Result};
+
+pub struct Window {
+    glfw: glfw::Glfw,
+    window: glfw::Window,
+    events: Receiver<(f64, glfw::WindowEvent)>,
+}
+
+impl Window {
+    pub fn new(width: u32, height: u32, title: &str) -> Result<Window> {
+        Window::create(width, height, title, true)
+    }
+
+    /// Creates a window that is never shown, for rendering into offscreen
+    /// framebuffers. Software drivers such as llvmpipe work as long as an X
+    /// server (e.g. Xvfb) is available.
+    pub fn new_hidden(width: u32, height: u32, title: &str) -> Result<Window> {
+        Window::create(width, height, title, false)
+    }
+
+    fn create(width: u32, height: u32, title: &str, visible: bool) -> Result<Window> {
+        let mut glfw = glfw::init(glfw::LOG_ERRORS).map_err(|e| Error::Context {
+            reason: format!("failed to initialize GLFW: {:?}", e),
+        })?;
+        glfw.window_hint(glfw::WindowHint::ContextVersion(4, 6));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+            glfw::OpenGlProfileHint::Core,
+        ));
+        glfw.window_hint(glfw::WindowHint::Visible(visible));
+
+        let (mut window, events) = glfw
+            .create_window(width, height, title, glfw::WindowMode::Windowed)
+            .ok_or_else(|| Error::Context {
+                reason: "failed to create GLFW window".into(),
+            })?;
+
+        window.make_current();
+        glfw.set_swap_interval(glfw::SwapInterval::None);
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+
+        Ok(Window {
+            glfw,
+            window,
+            events,
+        })
+    }
+
+    pub fn should_close(&self) -> bool {
+        self.window.should_close()
+    }
+
+    pub fn set_should_close(&mut self, value: bool) {
+        self.window.set_should_close(value);
+    }
+
+    pub fn get_time(&self) -> f32 {
+        self.glfw.get_time() as f32
+    }
+
+    pub fn get_framebuffer_size(&self) -> (i32, i32) {
+        self.window.get_framebuffer_size()
+    }
+
+    pub fn swap_buffers(&mut self) {
+        self.window.swap_buffers();
+    }
+
+    pub fn poll_events(&mut self) -> Vec<glfw::WindowEvent> {
+        self.glfw.poll_events();
+        glfw::flush_messages(&self.events)
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    pub fn handle(&mut self) -> &mut glfw::Window {
+        &mut self.window
+    }
+}
@@ -0,0 +1,119 @@
+use tofu::cgmath::prelude::*;
+use tofu::cgmath::{vec3, Deg, Matrix4, Point3};
+use tofu::{Aabb, BoundingSphere, Bounds};
+
+const TOLERANCE: f32 = 1e-5;
+
+fn assert_close(actual: Point3<f32>, expected: Point3<f32>) {
+    assert!(
+        (actual - expected).magnitude() < TOLERANCE,
+        "expected {:?}, got {:?}",
+        expected,
+        actual
+    );
+}
+
+fn unit_box() -> Aabb {
+    Aabb {
+        min: Point3::new(-1.0, -1.0, -1.0),
+        max: Point3::new(1.0, 1.0, 1.0),
+    }
+}
+
+#[test]
+fn aabb_from_points_and_union() {
+    assert_eq!(Aabb::from_points(Vec::new()), None);
+
+    let aabb = Aabb::from_points(vec![
+        Point3::new(1.0, -2.0, 0.0),
+        Point3::new(-1.0, 3.0, 0.5),
+        Point3::new(0.0, 0.0, -4.0),
+    ])
+    .unwrap();
+    assert_eq!(aabb.min, Point3::new(-1.0, -2.0, -4.0));
+    assert_eq!(aabb.max, Point3::new(1.0, 3.0, 0.5));
+    assert_eq!(aabb.center(), Point3::new(0.0, 0.5, -1.75));
+    assert_eq!(aabb.extents(), vec3(1.0, 2.5, 2.25));
+
+    let union = aabb.union(&Aabb::point(Point3::new(5.0, 0.0, 0.0)));
+    assert_eq!(union.min, aabb.min);
+    assert_eq!(union.max, Point3::new(5.0, 3.0, 0.5));
+}
+
+#[test]
+fn aabb_transformed_by_translation_and_scale_is_exact() {
+    let transform = Matrix4::from_translation(vec3(10.0, 0.0, -5.0))
+        * Matrix4::from_nonuniform_scale(2.0, 1.0, 0.5);
+
+    let aabb = unit_box().transformed(&transform);
+
+    assert_close(aabb.min, Point3::new(8.0, -1.0, -5.5));
+    assert_close(aabb.max, Point3::new(12.0, 1.0, -4.5));
+}
+
+#[test]
+fn aabb_transformed_by_rotation_contains_every_corner() {
+    let transform = Matrix4::from_angle_z(Deg(45.0));
+
+    let aabb = unit_box().transformed(&transform);
+
+    // The corners of the rotated box reach out to sqrt(2) along x and y.
+    let reach = 2.0f32.sqrt();
+    assert_close(aabb.min, Point3::new(-reach, -reach, -1.0));
+    assert_close(aabb.max, Point3::new(reach, reach, 1.0));
+}
+
+#[test]
+fn sphere_transformed_by_largest_scale() {
+    let sphere = BoundingSphere {
+        center: Point3::new(1.0, 0.0, 0.0),
+        radius: 2.0,
+    };
+    let transform = Matrix4::from_translation(vec3(0.0, 1.0, 0.0))
+        * Matrix4::from_angle_y(Deg(90.0))
+        * Matrix4::from_nonuniform_scale(1.0, 3.0, 0.5);
+
+    let transformed = sphere.transformed(&transform);
+
+    assert_close(transformed.center, Point3::new(0.0, 1.0, -1.0));
+    assert!((transformed.radius - 6.0).abs() < TOLERANCE);
+}
+
+#[test]
+fn bounds_from_points_contain_every_point() {
+    let points = vec![
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(4.0, 0.0, 0.0),
+        Point3::new(0.0, 2.0, 1.0),
+    ];
+
+    let bounds = Bounds::from_points(points.clone()).unwrap();
+
+    assert_eq!(bounds.sphere.center, bounds.aabb.center());
+    for point in points {
+        assert!((point - bounds.sphere.center).magnitude() <= bounds.sphere.radius + TOLERANCE);
+    }
+    assert_eq!(Bounds::from_points(Vec::new()), None);
+}
+
+#[test]
+fn merged_bounds_contain_both_spheres() {
+    let a = Bounds::from_points(vec![
+        Point3::new(-3.0, 0.0, 0.0),
+        Point3::new(-1.0, 0.0, 0.0),
+    ])
+    .unwrap();
+    let b =
+        Bounds::from_points(vec![Point3::new(2.0, 1.0, 0.0), Point3::new(4.0, 3.0, 0.0)]).unwrap();
+
+    let merged = Bounds::merge(&[a, b]).unwrap();
+
+    assert_eq!(merged.aabb, a.aabb.union(&b.aabb));
+    assert_eq!(merged.sphere.center, merged.aabb.center());
+    for part in &[a, b] {
+        let reach = (part.sphere.center - merged.sphere.center).magnitude() + part.sphere.radius;
+        assert!(reach <= merged.sphere.radius + TOLERANCE);
+    }
+    assert_eq!(Bounds::merge(&[]), None);
+    assert_eq!(Bounds::merge(&[a]), Some(a));
+}
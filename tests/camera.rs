@@ -0,0 +1,42 @@
+use tofu::cgmath::Point3;
+use tofu::{BoundingSphere, Camera};
+
+#[test]
+fn looking_at_the_position_keeps_the_orientation() {
+    let mut camera = Camera::new();
+    camera.make_perspective(50.0, 16.0 / 9.0);
+    camera.set_position(Point3::new(0.0, 1.0, 7.0));
+    camera.look_at(Point3::new(0.0, 1.0, 0.0));
+    let view = *camera.get_view();
+
+    camera.look_at(Point3::new(0.0, 1.0, 7.0));
+
+    assert_eq!(*camera.get_view(), view);
+    assert!(camera.get_view_projection().is_finite());
+}
+
+#[test]
+fn framed_spheres_fit_between_near_and_far_planes() {
+    for &radius in &[0.001, 1.0, 500.0] {
+        let mut camera = Camera::new();
+        camera.make_perspective(50.0, 16.0 / 9.0);
+        let sphere = BoundingSphere {
+            center: Point3::new(3.0, -2.0, 1.0),
+            radius,
+        };
+
+        camera.frame(&sphere);
+
+        let frustum = camera.frustum();
+        for plane in &frustum.planes[4..] {
+            let distance = plane.signed_distance(sphere.center);
+            assert!(
+                distance >= sphere.radius,
+                "radius {}: center {} from a depth plane",
+                radius,
+                distance
+            );
+        }
+        assert!(frustum.intersects_sphere(&sphere));
+    }
+}
@@ -0,0 +1,123 @@
+use tofu::cgmath::prelude::*;
+use tofu::cgmath::{perspective, vec3, Deg, Matrix4, Point3, Vector3};
+use tofu::{Aabb, BoundingSphere, Bounds, Frustum, Plane};
+
+const TOLERANCE: f32 = 1e-5;
+
+/// Looks down -Z from the origin with a 90 degree field of view, so the side
+/// planes are at 45 degrees, with near and far planes at 1 and 10.
+fn frustum() -> Frustum {
+    Frustum::from_matrix(&perspective(Deg(90.0), 1.0, 1.0, 10.0))
+}
+
+fn assert_plane(actual: &Plane, normal: Vector3<f32>, distance: f32) {
+    assert!(
+        (actual.normal - normal).magnitude() < TOLERANCE
+            && (actual.distance - distance).abs() < TOLERANCE,
+        "expected {:?} at {}, got {:?}",
+        normal,
+        distance,
+        actual
+    );
+}
+
+fn sphere(x: f32, y: f32, z: f32, radius: f32) -> BoundingSphere {
+    BoundingSphere {
+        center: Point3::new(x, y, z),
+        radius,
+    }
+}
+
+fn aabb(min: [f32; 3], max: [f32; 3]) -> Aabb {
+    Aabb {
+        min: Point3::from(min),
+        max: Point3::from(max),
+    }
+}
+
+#[test]
+fn planes_are_extracted_facing_inwards() {
+    let frustum = frustum();
+    let diagonal = 0.5f32.sqrt();
+
+    assert_plane(&frustum.planes[0], vec3(diagonal, 0.0, -diagonal), 0.0);
+    assert_plane(&frustum.planes[1], vec3(-diagonal, 0.0, -diagonal), 0.0);
+    assert_plane(&frustum.planes[2], vec3(0.0, diagonal, -diagonal), 0.0);
+    assert_plane(&frustum.planes[3], vec3(0.0, -diagonal, -diagonal), 0.0);
+    assert_plane(&frustum.planes[4], vec3(0.0, 0.0, -1.0), -1.0);
+    assert_plane(&frustum.planes[5], vec3(0.0, 0.0, 1.0), 10.0);
+}
+
+#[test]
+fn planes_follow_the_view() {
+    let view = Matrix4::look_at(
+        Point3::new(5.0, 0.0, 0.0),
+        Point3::new(5.0, 0.0, -1.0),
+        Vector3::unit_y(),
+    );
+    let frustum = Frustum::from_matrix(&(perspective(Deg(90.0), 1.0, 1.0, 10.0) * view));
+
+    assert!(frustum.intersects_sphere(&sphere(5.0, 0.0, -5.0, 0.5)));
+    assert!(!frustum.intersects_sphere(&sphere(-1.0, 0.0, -5.0, 0.5)));
+    let distance = frustum.planes[4].signed_distance(Point3::new(5.0, 0.0, -3.0));
+    assert!((distance - 2.0).abs() < TOLERANCE, "{}", distance);
+}
+
+#[test]
+fn spheres_inside_outside_and_straddling() {
+    let frustum = frustum();
+
+    assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -5.0, 1.0)));
+    // Straddling the near, far and left planes.
+    assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -0.5, 1.0)));
+    assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -10.5, 1.0)));
+    assert!(frustum.intersects_sphere(&sphere(-5.5, 0.0, -5.0, 1.0)));
+
+    assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, 5.0, 1.0)));
+    assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, -12.0, 1.0)));
+    assert!(!frustum.intersects_sphere(&sphere(-8.0, 0.0, -5.0, 1.0)));
+    assert!(!frustum.intersects_sphere(&sphere(0.0, 8.0, -5.0, 1.0)));
+}
+
+#[test]
+fn boxes_inside_outside_and_straddling() {
+    let frustum = frustum();
+
+    assert!(frustum.intersects_aabb(&aabb([-1.0, -1.0, -6.0], [1.0, 1.0, -4.0])));
+    // Straddling the near, far and left planes.
+    assert!(frustum.intersects_aabb(&aabb([-0.5, -0.5, -2.0], [0.5, 0.5, 0.0])));
+    assert!(frustum.intersects_aabb(&aabb([-0.5, -0.5, -11.0], [0.5, 0.5, -9.0])));
+    assert!(frustum.intersects_aabb(&aabb([-10.0, -0.5, -5.5], [-4.0, 0.5, -4.5])));
+    // Larger than the frustum.
+    assert!(frustum.intersects_aabb(&aabb([-50.0, -50.0, -50.0], [50.0, 50.0, 50.0])));
+
+    assert!(!frustum.intersects_aabb(&aabb([-1.0, -1.0, 1.0], [1.0, 1.0, 3.0])));
+    assert!(!frustum.intersects_aabb(&aabb([-1.0, -1.0, -13.0], [1.0, 1.0, -11.0])));
+    assert!(!frustum.intersects_aabb(&aabb([-10.0, -0.5, -5.5], [-6.0, 0.5, -4.5])));
+    assert!(!frustum.intersects_aabb(&aabb([-0.5, 6.0, -5.5], [0.5, 8.0, -4.5])));
+}
+
+#[test]
+fn bounds_inside_outside_and_straddling() {
+    let frustum = frustum();
+    let inside = Bounds::from_points(vec![
+        Point3::new(-1.0, 0.0, -5.0),
+        Point3::new(1.0, 0.0, -5.0),
+    ])
+    .unwrap();
+    let outside = Bounds::from_points(vec![
+        Point3::new(-1.0, 0.0, 5.0),
+        Point3::new(1.0, 0.0, 5.0),
+    ])
+    .unwrap();
+
+    assert!(frustum.intersects(&inside));
+    assert!(!frustum.intersects(&outside));
+
+    let straddling = Bounds::from_points(vec![
+        Point3::new(0.0, 0.0, -9.0),
+        Point3::new(0.0, 0.0, -11.0),
+    ])
+    .unwrap();
+    assert!(frustum.intersects(&straddling));
+}
@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tofu::{Error, LoadOptions, Model};
+
+/// Writes a glTF file with one triangle primitive over three vertices and
+/// `indices`, in a fresh directory named after the test, and returns its path.
+fn write_gltf(test: &str, indices: &[u16]) -> PathBuf {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("gltf")
+        .join(test);
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    let mut buffer: Vec<u8> = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+        .iter()
+        .flat_map(|f| f.to_le_bytes())
+        .collect();
+    buffer.extend(indices.iter().flat_map(|i| i.to_le_bytes()));
+    fs::write(directory.join("triangle.bin"), &buffer).unwrap();
+
+    let gltf = format!(
+        r#"{{
+  "asset": {{ "version": "2.0" }},
+  "buffers": [{{ "uri": "triangle.bin", "byteLength": {buffer_length} }}],
+  "bufferViews": [
+    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+    {{ "buffer": 0, "byteOffset": 36, "byteLength": {index_length} }}
+  ],
+  "accessors": [
+    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+       "min": [0, 0, 0], "max": [1, 1, 0] }},
+    {{ "bufferView": 1, "componentType": 5123, "count": {index_count}, "type": "SCALAR" }}
+  ],
+  "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1 }}] }}],
+  "nodes": [{{ "mesh": 0 }}],
+  "scenes": [{{ "nodes": [0] }}]
+}}"#,
+        buffer_length = buffer.len(),
+        index_length = indices.len() * 2,
+        index_count = indices.len(),
+    );
+    let filepath = directory.join("triangle.gltf");
+    fs::write(&filepath, gltf).unwrap();
+    filepath
+}
+
+/// Loads without creating any GL objects, which only works for files that
+/// fail before their first mesh is uploaded.
+fn load_error(filepath: &Path) -> String {
+    let options = LoadOptions {
+        mesh_cache: None,
+        ..LoadOptions::default()
+    };
+
+    match Model::with_options(filepath.to_str().unwrap(), &options) {
+        Err(error @ Error::MeshParse { .. }) => error.to_string(),
+        Err(error) => panic!("expected a mesh parse error, got {}", error),
+        Ok(_) => panic!("expected a mesh parse error"),
+    }
+}
+
+#[test]
+fn out_of_range_indices_are_an_error() {
+    let filepath = write_gltf("out_of_range", &[0, 1, 3, 0]);
+
+    assert!(load_error(&filepath)
+        .ends_with("primitive 0 of mesh 0 references vertex 3 but there are only 3 vertices"));
+}
+
+#[test]
+fn incomplete_triangles_are_an_error() {
+    let filepath = write_gltf("incomplete", &[0, 1, 2, 0]);
+
+    assert!(load_error(&filepath).ends_with("has 4 indices, which is not a multiple of 3"));
+}
@@ -0,0 +1,216 @@
+//! Golden-image regression tests.
+//!
+//! Renders the bundled models offscreen with a fixed camera and time and
+//! compares the result against reference PNGs in `tests/golden`. Run with
+//! `TOFU_BLESS=1` to (re)write the references after an intentional change.
+//!
+//! Runs without the libtest harness so that every render happens on the main
+//! thread with a single GL context.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use tofu::cgmath::Point3;
+use tofu::{
+    Error, HeadlessRenderer, LoadOptions, Model, RgbaImage, ShaderBuilder, ShaderPermutations,
+};
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+const TIME: f32 = 0.0;
+
+/// Largest per-channel difference that still counts as a matching pixel.
+const CHANNEL_TOLERANCE: u8 = 3;
+/// Fraction of pixels allowed to exceed the tolerance, to absorb rasterization
+/// differences between drivers.
+const MAX_MISMATCH_RATIO: f64 = 0.001;
+
+struct GoldenCase {
+    name: &'static str,
+    model_filepath: &'static str,
+    /// Whether the model's textures are in the repository.
+    textures: bool,
+}
+
+const CASES: &[GoldenCase] = &[
+    GoldenCase {
+        name: "normal_test",
+        model_filepath: "assets/models/normal_test/normal_test.obj",
+        textures: true,
+    },
+    GoldenCase {
+        name: "ufnscjdga_LOD0",
+        model_filepath: "assets/models/3d_other_ufnscjdga/ufnscjdga_LOD0.obj",
+        textures: false,
+    },
+];
+
+struct Comparison {
+    mismatched: usize,
+    max_difference: u8,
+    diff: RgbaImage,
+}
+
+fn main() {
+    let bless = env::var_os("TOFU_BLESS").is_some();
+
+    let mut headless = match HeadlessRenderer::new(WIDTH, HEIGHT) {
+        Ok(headless) => headless,
+        Err(error @ Error::Context { .. }) => {
+            println!("skipping golden tests: {}", error);
+            return;
+        }
+        Err(error) => {
+            eprintln!("failed to set up headless renderer: {}", error);
+            process::exit(1);
+        }
+    };
+
+    headless.camera().set_position(Point3::new(0.0, 1.0, 7.0));
+    headless.camera().look_at(Point3::new(0.0, 1.0, 0.0));
+
+    let mut permutations = ShaderPermutations::new(
+        ShaderBuilder::new()
+            .vertex("assets/shaders/basic.vs")
+            .fragment("assets/shaders/basic.fs")
+            .program_cache(None),
+    );
+
+    println!("\nrunning {} golden tests", CASES.len());
+
+    let mut failures = 0;
+    for case in CASES {
+        match run_case(&mut headless, &mut permutations, case, bless) {
+            Ok(message) => println!("golden {} ... ok{}", case.name, message),
+            Err(message) => {
+                println!("golden {} ... FAILED\n    {}", case.name, message);
+                failures += 1;
+            }
+        }
+    }
+
+    println!(
+        "\ngolden test result: {}. {} passed; {} failed\n",
+        if failures == 0 { "ok" } else { "FAILED" },
+        CASES.len() - failures,
+        failures
+    );
+
+    if failures > 0 {
+        process::exit(1);
+    }
+}
+
+fn run_case(
+    headless: &mut HeadlessRenderer,
+    permutations: &mut ShaderPermutations,
+    case: &GoldenCase,
+    bless: bool,
+) -> Result<String, String> {
+    // Caches would let stale results from other builds into the image, and
+    // without generated levels every mesh is drawn at full detail.
+    let options = LoadOptions {
+        lods: None,
+        mesh_cache: None,
+        textures: case.textures,
+        ..LoadOptions::default()
+    };
+    let model = Model::with_options(case.model_filepath, &options).map_err(|e| e.to_string())?;
+    let actual = headless
+        .render_permutations(&model, permutations, &tofu::turntable_matrix(TIME), TIME)
+        .map_err(|e| e.to_string())?;
+
+    let reference_filepath = Path::new("tests/golden").join(format!("{}.png", case.name));
+
+    if bless {
+        actual
+            .save_png(&reference_filepath)
+            .map_err(|e| e.to_string())?;
+        return Ok(format!(" (blessed {})", reference_filepath.display()));
+    }
+
+    let expected = match RgbaImage::load(&reference_filepath) {
+        Ok(expected) => expected,
+        Err(Error::Io { .. }) => {
+            return Err(format!(
+                "missing reference {}, run with TOFU_BLESS=1 to create it",
+                reference_filepath.display()
+            ))
+        }
+        Err(error) => return Err(error.to_string()),
+    };
+
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(format!(
+            "size mismatch: expected {}x{}, got {}x{}",
+            expected.width, expected.height, actual.width, actual.height
+        ));
+    }
+
+    let comparison = compare(&expected, &actual);
+    let pixel_count = (actual.width * actual.height) as usize;
+    if comparison.mismatched as f64 > pixel_count as f64 * MAX_MISMATCH_RATIO {
+        let actual_filepath = output_filepath(case.name, "actual");
+        let diff_filepath = output_filepath(case.name, "diff");
+        actual
+            .save_png(&actual_filepath)
+            .map_err(|e| e.to_string())?;
+        comparison
+            .diff
+            .save_png(&diff_filepath)
+            .map_err(|e| e.to_string())?;
+
+        return Err(format!(
+            "{} of {} pixels differ (max channel difference {}), see {} and {}",
+            comparison.mismatched,
+            pixel_count,
+            comparison.max_difference,
+            actual_filepath.display(),
+            diff_filepath.display()
+        ));
+    }
+
+    Ok(String::new())
+}
+
+/// Compares two images of equal size. The diff image shows the reference
+/// dimmed to grayscale with mismatching pixels highlighted in red.
+fn compare(expected: &RgbaImage, actual: &RgbaImage) -> Comparison {
+    let mut mismatched = 0;
+    let mut max_difference = 0;
+    let mut diff_pixels = Vec::with_capacity(expected.pixels.len());
+
+    for (e, a) in expected
+        .pixels
+        .chunks_exact(4)
+        .zip(actual.pixels.chunks_exact(4))
+    {
+        let difference = e
+            .iter()
+            .zip(a)
+            .map(|(&e, &a)| (e as i16 - a as i16).unsigned_abs() as u8)
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+
+        if difference > CHANNEL_TOLERANCE {
+            mismatched += 1;
+            diff_pixels.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma = (e[0] as u32 * 3 + e[1] as u32 * 6 + e[2] as u32) / 10;
+            let dimmed = (luma / 3) as u8;
+            diff_pixels.extend_from_slice(&[dimmed, dimmed, dimmed, 255]);
+        }
+    }
+
+    Comparison {
+        mismatched,
+        max_difference,
+        diff: RgbaImage::new(expected.width, expected.height, diff_pixels),
+    }
+}
+
+fn output_filepath(name: &str, suffix: &str) -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.{}.png", name, suffix))
+}
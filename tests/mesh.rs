@@ -0,0 +1,13 @@
+use tofu::cgmath::prelude::*;
+use tofu::cgmath::Point3;
+use tofu::Mesh;
+
+#[test]
+fn empty_mesh_is_not_uploaded() {
+    // Without a GL context any upload or delete would panic.
+    let mesh = Mesh::new(Vec::new(), Vec::new(), Vec::new());
+
+    assert_eq!(mesh.lod_count(), 1);
+    assert_eq!(mesh.bounds().sphere.center, Point3::origin());
+    assert_eq!(mesh.bounds().sphere.radius, 0.0);
+}
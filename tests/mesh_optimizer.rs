@@ -0,0 +1,117 @@
+use tofu::cgmath::vec3;
+use tofu::{analyze_vertex_cache, optimize_mesh, optimize_vertex_cache, Vertex, VERTEX_CACHE_SIZE};
+
+const GRID_SIZE: u32 = 32;
+
+/// A flat grid of `GRID_SIZE` by `GRID_SIZE` quads, with its triangles in
+/// row-major order.
+fn grid() -> (Vec<Vertex>, Vec<u32>) {
+    let vertices = (0..=GRID_SIZE)
+        .flat_map(|y| (0..=GRID_SIZE).map(move |x| (x, y)))
+        .map(|(x, y)| Vertex {
+            position: vec3(x as f32, y as f32, 0.0),
+            normal: vec3(0.0, 0.0, 1.0),
+            ..Vertex::default()
+        })
+        .collect();
+
+    let index = |x: u32, y: u32| y * (GRID_SIZE + 1) + x;
+    let indices = (0..GRID_SIZE)
+        .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            vec![
+                index(x, y),
+                index(x + 1, y),
+                index(x + 1, y + 1),
+                index(x, y),
+                index(x + 1, y + 1),
+                index(x, y + 1),
+            ]
+        })
+        .collect();
+
+    (vertices, indices)
+}
+
+/// Reorders triangles with a fixed linear congruential shuffle.
+fn shuffle_triangles(indices: &[u32]) -> Vec<u32> {
+    let mut triangles: Vec<&[u32]> = indices.chunks_exact(3).collect();
+    let mut state: u32 = 12345;
+    for i in (1..triangles.len()).rev() {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        triangles.swap(i, (state >> 16) as usize % (i + 1));
+    }
+    triangles.concat()
+}
+
+/// Every triangle as grid coordinates, rotated to start at its smallest
+/// corner so that the winding is kept, in sorted order.
+fn triangle_set(vertices: &[Vertex], indices: &[u32]) -> Vec<[(i32, i32); 3]> {
+    let mut triangles: Vec<[(i32, i32); 3]> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let corner = |i: usize| {
+                let position = vertices[triangle[i] as usize].position;
+                (position.x as i32, position.y as i32)
+            };
+            let mut corners = [corner(0), corner(1), corner(2)];
+            let smallest = (0..3).min_by_key(|&i| corners[i]).unwrap();
+            corners.rotate_left(smallest);
+            corners
+        })
+        .collect();
+    triangles.sort_unstable();
+    triangles
+}
+
+#[test]
+fn optimize_mesh_keeps_triangles_and_winding() {
+    let (vertices, indices) = grid();
+    let indices = shuffle_triangles(&indices);
+
+    let (optimized_vertices, optimized_indices) = optimize_mesh(&vertices, &indices);
+
+    assert_eq!(optimized_vertices.len(), vertices.len());
+    assert_eq!(
+        triangle_set(&optimized_vertices, &optimized_indices),
+        triangle_set(&vertices, &indices)
+    );
+}
+
+#[test]
+fn optimize_mesh_welds_duplicate_vertices() {
+    let (vertices, indices) = grid();
+    // Every triangle corner as its own vertex.
+    let unwelded: Vec<Vertex> = indices.iter().map(|&i| vertices[i as usize]).collect();
+    let unwelded_indices: Vec<u32> = (0..unwelded.len() as u32).collect();
+
+    let (welded, welded_indices) = optimize_mesh(&unwelded, &unwelded_indices);
+
+    assert_eq!(welded.len(), vertices.len());
+    assert_eq!(
+        triangle_set(&welded, &welded_indices),
+        triangle_set(&vertices, &indices)
+    );
+}
+
+#[test]
+fn optimization_does_not_worsen_acmr() {
+    let (vertices, row_major) = grid();
+    let shuffled = shuffle_triangles(&row_major);
+
+    for indices in &[row_major, shuffled] {
+        let before = analyze_vertex_cache(indices, vertices.len(), VERTEX_CACHE_SIZE);
+
+        let cache_optimized = optimize_vertex_cache(indices, vertices.len(), VERTEX_CACHE_SIZE);
+        let after = analyze_vertex_cache(&cache_optimized, vertices.len(), VERTEX_CACHE_SIZE);
+        assert!(after.acmr <= before.acmr, "{} -> {}", before, after);
+
+        let (optimized_vertices, optimized_indices) = optimize_mesh(&vertices, indices);
+        let after = analyze_vertex_cache(
+            &optimized_indices,
+            optimized_vertices.len(),
+            VERTEX_CACHE_SIZE,
+        );
+        assert!(after.acmr <= before.acmr, "{} -> {}", before, after);
+    }
+}
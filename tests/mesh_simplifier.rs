@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use tofu::cgmath::prelude::*;
+use tofu::cgmath::{vec3, Vector3};
+use tofu::{generate_lods, simplify, LodGeneration, Vertex};
+
+const SUBDIVISIONS: u32 = 16;
+
+/// A square of `SUBDIVISIONS` by `SUBDIVISIONS` quads spanned by `u` and `v`
+/// from `origin`, facing `u.cross(v)`, appended with its own vertices.
+fn add_grid(
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u32>,
+    origin: Vector3<f32>,
+    u: Vector3<f32>,
+    v: Vector3<f32>,
+) {
+    let base = vertices.len() as u32;
+    let normal = u.cross(v).normalize();
+    let step = 1.0 / SUBDIVISIONS as f32;
+
+    for y in 0..=SUBDIVISIONS {
+        for x in 0..=SUBDIVISIONS {
+            vertices.push(Vertex {
+                position: origin + u * (x as f32 * step) + v * (y as f32 * step),
+                normal,
+                ..Vertex::default()
+            });
+        }
+    }
+
+    let index = |x: u32, y: u32| base + y * (SUBDIVISIONS + 1) + x;
+    for y in 0..SUBDIVISIONS {
+        for x in 0..SUBDIVISIONS {
+            indices.extend_from_slice(&[
+                index(x, y),
+                index(x + 1, y),
+                index(x + 1, y + 1),
+                index(x, y),
+                index(x + 1, y + 1),
+                index(x, y + 1),
+            ]);
+        }
+    }
+}
+
+fn grid() -> (Vec<Vertex>, Vec<u32>) {
+    let (mut vertices, mut indices) = (Vec::new(), Vec::new());
+    add_grid(
+        &mut vertices,
+        &mut indices,
+        Vector3::zero(),
+        Vector3::unit_x(),
+        Vector3::unit_y(),
+    );
+    (vertices, indices)
+}
+
+/// A closed unit cube with hard edges: every face has its own vertices.
+fn cube() -> (Vec<Vertex>, Vec<u32>) {
+    let (mut vertices, mut indices) = (Vec::new(), Vec::new());
+    let (x, y, z) = (Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z());
+    let faces = [
+        (Vector3::zero(), y, x),
+        (z, x, y),
+        (Vector3::zero(), z, y),
+        (x, y, z),
+        (Vector3::zero(), x, z),
+        (y, z, x),
+    ];
+    for &(origin, u, v) in &faces {
+        add_grid(&mut vertices, &mut indices, origin, u, v);
+    }
+    (vertices, indices)
+}
+
+/// Identifies vertices by position, so that faces with their own vertices
+/// still share edges.
+fn position_ids(vertices: &[Vertex]) -> Vec<usize> {
+    let mut ids = HashMap::new();
+    vertices
+        .iter()
+        .map(|v| {
+            let key = [v.position.x, v.position.y, v.position.z].map(f32::to_bits);
+            let next = ids.len();
+            *ids.entry(key).or_insert(next)
+        })
+        .collect()
+}
+
+/// Asserts that the indices are in range, no triangle is degenerate and every
+/// edge is used at most once per direction, which holds for any consistently
+/// wound manifold. With `closed`, every edge also has to be matched by one in
+/// the opposite direction.
+fn assert_manifold(vertices: &[Vertex], indices: &[u32], closed: bool) {
+    assert_eq!(indices.len() % 3, 0);
+    assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+
+    let ids = position_ids(vertices);
+    let mut edges = HashMap::new();
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| ids[triangle[i] as usize]);
+        assert!(
+            a != b && b != c && c != a,
+            "degenerate triangle {:?}",
+            triangle
+        );
+
+        for edge in &[(a, b), (b, c), (c, a)] {
+            *edges.entry(*edge).or_insert(0) += 1;
+        }
+    }
+
+    for (&(a, b), &count) in &edges {
+        assert_eq!(count, 1, "edge {:?} is used {} times", (a, b), count);
+        if closed {
+            assert!(edges.contains_key(&(b, a)), "edge {:?} is open", (a, b));
+        }
+    }
+}
+
+#[test]
+fn grid_reaches_the_target() {
+    let (vertices, indices) = grid();
+    let target = indices.len() / 4;
+
+    let (simplified, error) = simplify(&vertices, &indices, target, 0.01);
+
+    assert!(
+        simplified.len() <= target,
+        "{} > {}",
+        simplified.len(),
+        target
+    );
+    assert!(!simplified.is_empty());
+    assert!(
+        error < 1e-3,
+        "a flat grid simplifies without error, got {}",
+        error
+    );
+    assert_manifold(&vertices, &simplified, false);
+}
+
+#[test]
+fn cube_stays_closed() {
+    let (vertices, indices) = cube();
+    assert_manifold(&vertices, &indices, true);
+    let target = indices.len() / 8;
+
+    let (simplified, _) = simplify(&vertices, &indices, target, 0.01);
+
+    assert!(
+        simplified.len() <= target,
+        "{} > {}",
+        simplified.len(),
+        target
+    );
+    assert_manifold(&vertices, &simplified, true);
+}
+
+#[test]
+fn curved_surfaces_respect_the_error_limit() {
+    let (mut vertices, indices) = grid();
+    for vertex in &mut vertices {
+        let p = vertex.position;
+        vertex.position.z = ((p.x - 0.5).powi(2) + (p.y - 0.5).powi(2)) * 0.5;
+    }
+
+    let (simplified, error) = simplify(&vertices, &indices, 0, 0.01);
+
+    assert!(error <= 0.01);
+    assert!(
+        simplified.len() > 6,
+        "a curved surface cannot become a quad"
+    );
+    assert_manifold(&vertices, &simplified, false);
+}
+
+#[test]
+fn non_finite_positions_do_not_panic() {
+    let (mut vertices, indices) = grid();
+    vertices[20].position = vec3(f32::NAN, 0.0, 0.0);
+
+    let (simplified, _) = simplify(&vertices, &indices, indices.len() / 4, 0.01);
+
+    assert!(simplified.iter().all(|&i| (i as usize) < vertices.len()));
+}
+
+#[test]
+fn generated_levels_get_coarser() {
+    let (vertices, indices) = cube();
+
+    let lods = generate_lods(&vertices, &indices, &LodGeneration::default());
+
+    assert!(!lods.is_empty());
+    let mut previous = indices.len();
+    for lod in &lods {
+        assert!(lod.indices.len() < previous);
+        assert_manifold(&vertices, &lod.indices, true);
+        previous = lod.indices.len();
+    }
+}
@@ -0,0 +1,151 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tofu::{Error, ShaderPreprocessor};
+
+/// Writes `files` into a fresh directory named after the test and returns it.
+fn shader_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("preprocessor")
+        .join(test);
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+
+    for (name, code) in files {
+        fs::write(directory.join(name), code).unwrap();
+    }
+
+    directory
+}
+
+#[test]
+fn includes_each_file_once() {
+    let directory = shader_dir(
+        "include_once",
+        &[
+            (
+                "main.glsl",
+                "#version 460\n#include \"a.glsl\"\n#include \"b.glsl\"\n",
+            ),
+            ("a.glsl", "#include \"common.glsl\"\nfloat a;\n"),
+            ("b.glsl", "#include \"common.glsl\"\nfloat b;\n"),
+            ("common.glsl", "float common;\n"),
+        ],
+    );
+
+    let processed = ShaderPreprocessor::new()
+        .process(directory.join("main.glsl"))
+        .unwrap();
+
+    assert_eq!(processed.source.matches("float common;").count(), 1);
+    assert!(processed.source.contains("float a;"));
+    assert!(processed.source.contains("float b;"));
+    assert_eq!(processed.files.len(), 4);
+}
+
+#[test]
+fn include_cycle_is_an_error() {
+    let directory = shader_dir(
+        "include_cycle",
+        &[
+            ("main.glsl", "#version 460\n#include \"a.glsl\"\n"),
+            ("a.glsl", "#include \"b.glsl\"\n"),
+            ("b.glsl", "float b;\n#include \"a.glsl\"\n"),
+        ],
+    );
+
+    match ShaderPreprocessor::new().process(directory.join("main.glsl")) {
+        Err(Error::ShaderInclude { path, line, .. }) => {
+            assert_eq!(path, directory.join("b.glsl"));
+            assert_eq!(line, 2);
+        }
+        Err(error) => panic!("expected an include error, got {}", error),
+        Ok(_) => panic!("expected an include error"),
+    }
+}
+
+#[test]
+fn defines_follow_version_and_line_directives_track_files() {
+    let directory = shader_dir(
+        "defines",
+        &[
+            (
+                "main.glsl",
+                "#version 460\n#include \"common.glsl\"\nvoid main() {}\n",
+            ),
+            ("common.glsl", "float common;\n"),
+        ],
+    );
+
+    let processed = ShaderPreprocessor::new()
+        .define("USE_NORMAL_MAP", "1")
+        .process(directory.join("main.glsl"))
+        .unwrap();
+
+    assert_eq!(
+        processed.source,
+        "#version 460\n\
+         #define USE_NORMAL_MAP 1\n\
+         #line 2 0\n\
+         #line 1 1\n\
+         float common;\n\
+         #line 3 0\n\
+         void main() {}\n"
+    );
+
+    let main = directory.join("main.glsl");
+    let common = directory.join("common.glsl");
+    assert_eq!(processed.source_location(1), Some((main.as_path(), 1)));
+    assert_eq!(processed.source_location(5), Some((common.as_path(), 1)));
+    assert_eq!(processed.source_location(7), Some((main.as_path(), 3)));
+}
+
+#[test]
+fn remap_log_names_included_files() {
+    let directory = shader_dir(
+        "remap_log",
+        &[
+            ("main.glsl", "#version 460\n#include \"common.glsl\"\n"),
+            ("common.glsl", "float common;\nfloat broken\n"),
+        ],
+    );
+
+    let processed = ShaderPreprocessor::new()
+        .process(directory.join("main.glsl"))
+        .unwrap();
+
+    let common = directory.join("common.glsl");
+    assert_eq!(
+        processed.remap_log("1:2(13): error: syntax error\n1(2) : error C0000: syntax error"),
+        format!(
+            "{0}:2(13): error: syntax error\n{0}:2 : error C0000: syntax error",
+            common.display()
+        )
+    );
+}
+
+#[test]
+fn defines_follow_a_version_after_leading_comments() {
+    let directory = shader_dir(
+        "leading_comment",
+        &[(
+            "main.glsl",
+            "// Basic shader.\n\n#version 460\nvoid main() {}\n",
+        )],
+    );
+
+    let processed = ShaderPreprocessor::new()
+        .define("USE_NORMAL_MAP", "1")
+        .process(directory.join("main.glsl"))
+        .unwrap();
+
+    assert_eq!(
+        processed.source,
+        "// Basic shader.\n\
+         \n\
+         #version 460\n\
+         #define USE_NORMAL_MAP 1\n\
+         #line 4 0\n\
+         void main() {}\n"
+    );
+}
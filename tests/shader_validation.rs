@@ -0,0 +1,312 @@
+//! Offline shader validation.
+//!
+//! Parses and validates every shader under `assets/shaders` with naga, so
+//! broken GLSL is caught without a GL context. Sources go through tofu's own
+//! preprocessor first and errors are reported against the original files.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use naga::front::glsl::{Frontend, Options};
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use naga::{Binding, Module, ShaderStage, TypeInner};
+
+use tofu::{MaterialFeatures, PreprocessedSource, ShaderPreprocessor};
+
+const SHADER_DIRECTORY: &str = "assets/shaders";
+
+/// Feature sets every shader is checked with: no optional inputs, all of them
+/// and the glTF packed map, which between them cover both sides of every
+/// `#ifdef`.
+fn feature_sets() -> Vec<MaterialFeatures> {
+    vec![
+        MaterialFeatures::NONE,
+        MaterialFeatures::HAS_ALBEDO_MAP
+            | MaterialFeatures::HAS_NORMAL_MAP
+            | MaterialFeatures::HAS_ROUGHNESS_MAP
+            | MaterialFeatures::HAS_METALLIC_MAP
+            | MaterialFeatures::HAS_ALPHA_MASK
+            | MaterialFeatures::HAS_OCCLUSION_MAP
+            | MaterialFeatures::HAS_EMISSIVE_MAP,
+        MaterialFeatures::HAS_METALLIC_ROUGHNESS_MAP,
+    ]
+}
+
+#[test]
+fn shaders_parse_and_validate() {
+    let mut errors = Vec::new();
+
+    for filepath in shader_files() {
+        let stage = match shader_stage(&filepath) {
+            Some(stage) => stage,
+            // naga's GLSL front end only handles vertex, fragment and compute.
+            None => continue,
+        };
+
+        for features in feature_sets() {
+            if let Err(error) = compile(&filepath, stage, features) {
+                errors.push(error);
+            }
+        }
+    }
+
+    assert!(errors.is_empty(), "\n{}", errors.join("\n"));
+}
+
+#[test]
+fn vertex_outputs_match_fragment_inputs() {
+    let mut errors = Vec::new();
+
+    for vertex_filepath in shader_files() {
+        if shader_stage(&vertex_filepath) != Some(ShaderStage::Vertex) {
+            continue;
+        }
+
+        // Anything between the two stages changes the interface.
+        if ["gs", "tcs", "tes"]
+            .iter()
+            .any(|extension| vertex_filepath.with_extension(extension).exists())
+        {
+            continue;
+        }
+
+        let fragment_filepath = vertex_filepath.with_extension("fs");
+        if !fragment_filepath.exists() {
+            continue;
+        }
+
+        for features in feature_sets() {
+            let (vertex, fragment) = match (
+                compile(&vertex_filepath, ShaderStage::Vertex, features),
+                compile(&fragment_filepath, ShaderStage::Fragment, features),
+            ) {
+                (Ok(vertex), Ok(fragment)) => (vertex, fragment),
+                // Reported by `shaders_parse_and_validate`.
+                _ => continue,
+            };
+
+            let outputs = vertex_outputs(&vertex);
+            for (name, input_type) in fragment_inputs(&fragment) {
+                match outputs.get(&name) {
+                    None => errors.push(format!(
+                        "{}: fragment input '{}' is not written by {}",
+                        fragment_filepath.display(),
+                        name,
+                        vertex_filepath.display()
+                    )),
+                    Some(output_type) if *output_type != input_type => errors.push(format!(
+                        "{}: fragment input '{}' is {:?} but {} writes {:?}",
+                        fragment_filepath.display(),
+                        name,
+                        input_type,
+                        vertex_filepath.display(),
+                        output_type
+                    )),
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+
+    assert!(errors.is_empty(), "\n{}", errors.join("\n"));
+}
+
+fn shader_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_shader_files(Path::new(SHADER_DIRECTORY), &mut files);
+    files.sort();
+    files
+}
+
+fn collect_shader_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_shader_files(&path, files);
+        } else if path.extension().is_some_and(|e| e != "glsl") {
+            // `.glsl` files are headers that are only valid when included.
+            files.push(path);
+        }
+    }
+}
+
+fn shader_stage(filepath: &Path) -> Option<ShaderStage> {
+    match filepath.extension()?.to_str()? {
+        "vs" => Some(ShaderStage::Vertex),
+        "fs" => Some(ShaderStage::Fragment),
+        "cs" | "comp" => Some(ShaderStage::Compute),
+        _ => None,
+    }
+}
+
+fn compile(
+    filepath: &Path,
+    stage: ShaderStage,
+    features: MaterialFeatures,
+) -> Result<Module, String> {
+    let mut preprocessor = ShaderPreprocessor::new();
+    for name in features.defines() {
+        preprocessor.define(name, "1");
+    }
+
+    let preprocessed = preprocessor.process(filepath).map_err(|e| e.to_string())?;
+
+    // naga's preprocessor does not understand source string numbers, so the
+    // `#line` directives are blanked out, keeping the line count intact.
+    let source = preprocessed
+        .source
+        .lines()
+        .map(|line| if line.starts_with("#line") { "" } else { line })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let source = split_combined_samplers(&source);
+
+    let module = Frontend::default()
+        .parse(&Options::from(stage), &source)
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| {
+                    let line = error.meta.location(&source).line_number as usize;
+                    format!(
+                        "{} ({:?}): {}",
+                        location(&preprocessed, filepath, line),
+                        features,
+                        error.kind
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+
+    Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|error| {
+            let line = error
+                .spans()
+                .next()
+                .map(|(span, _)| span.location(&source).line_number as usize);
+
+            format!(
+                "{} ({:?}): {}",
+                match line {
+                    Some(line) => location(&preprocessed, filepath, line),
+                    None => filepath.display().to_string(),
+                },
+                features,
+                error.as_inner()
+            )
+        })?;
+
+    Ok(module)
+}
+
+/// naga only reads Vulkan-style GLSL, where textures and samplers are separate
+/// objects, so every `uniform sampler2D` becomes a texture and a sampler
+/// combined again wherever it is used. Each declaration stays on its line.
+fn split_combined_samplers(source: &str) -> String {
+    let names: Vec<&str> = source
+        .lines()
+        .filter_map(|line| {
+            line.trim()
+                .strip_prefix("uniform sampler2D ")?
+                .strip_suffix(';')
+                .map(str::trim)
+        })
+        .collect();
+
+    source
+        .lines()
+        .map(|line| {
+            let declared = line
+                .trim()
+                .strip_prefix("uniform sampler2D ")
+                .and_then(|rest| rest.strip_suffix(';'))
+                .and_then(|name| names.iter().position(|n| *n == name.trim()));
+            if let Some(index) = declared {
+                let name = names[index];
+                return format!(
+                    "layout(set = 0, binding = {}) uniform texture2D {}_texture; \
+                     layout(set = 0, binding = {}) uniform sampler {}_sampler;",
+                    index * 2,
+                    name,
+                    index * 2 + 1,
+                    name
+                );
+            }
+
+            let mut line = line.to_string();
+            for name in &names {
+                line = replace_identifier(
+                    &line,
+                    name,
+                    &format!("sampler2D({0}_texture, {0}_sampler)", name),
+                );
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replaces whole-word occurrences of the identifier `name`.
+fn replace_identifier(line: &str, name: &str, replacement: &str) -> String {
+    let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut result = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(name) {
+        let end = start + name.len();
+        let before = rest[..start].chars().next_back();
+        let after = rest[end..].chars().next();
+        result.push_str(&rest[..start]);
+        if before.is_some_and(is_identifier) || after.is_some_and(is_identifier) {
+            result.push_str(name);
+        } else {
+            result.push_str(replacement);
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn location(preprocessed: &PreprocessedSource, filepath: &Path, line: usize) -> String {
+    match preprocessed.source_location(line) {
+        Some((file, line)) => format!("{}:{}", file.display(), line),
+        None => format!("{}:{}", filepath.display(), line),
+    }
+}
+
+fn vertex_outputs(module: &Module) -> HashMap<String, TypeInner> {
+    let mut outputs = HashMap::new();
+
+    let result = match module.entry_points[0].function.result.as_ref() {
+        Some(result) => result,
+        None => return outputs,
+    };
+
+    if let TypeInner::Struct { members, .. } = &module.types[result.ty].inner {
+        for member in members {
+            if let (Some(name), Some(Binding::Location { .. })) = (&member.name, &member.binding) {
+                outputs.insert(name.clone(), module.types[member.ty].inner.clone());
+            }
+        }
+    }
+
+    outputs
+}
+
+fn fragment_inputs(module: &Module) -> Vec<(String, TypeInner)> {
+    module.entry_points[0]
+        .function
+        .arguments
+        .iter()
+        .filter(|argument| matches!(argument.binding, Some(Binding::Location { .. })))
+        .filter_map(|argument| {
+            let name = argument.name.clone()?;
+            Some((name, module.types[argument.ty].inner.clone()))
+        })
+        .collect()
+}
@@ -0,0 +1,174 @@
+//! Checks generated tangents against hand-derived reference values and the
+//! invariants every tangent frame has to satisfy.
+//!
+//! The references for `normal_test.obj` were derived by hand from its UV
+//! layout, Blender's default cube unwrap: every face is planar with an affine
+//! UV mapping, so MikkTSpace gives each face the direction of increasing U
+//! projected onto the face, with positive handedness.
+
+use tofu::cgmath::prelude::*;
+use tofu::cgmath::{vec2, vec3, Vector3};
+use tofu::{compute_tangents, import_obj_meshes, LoadOptions, Vertex};
+
+const TOLERANCE: f32 = 1e-4;
+/// Normals read from files are rounded to a few decimals, so tangents are only
+/// orthogonal to them up to that precision.
+const ORTHOGONALITY_TOLERANCE: f32 = 1e-3;
+
+/// Face normal, tangent and binormal headedness of every cube face.
+const NORMAL_TEST_REFERENCE: &[([f32; 3], [f32; 3], f32)] = &[
+    ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0], 1.0),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0], 1.0),
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], 1.0),
+    ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0], 1.0),
+    ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], 1.0),
+    ([0.0, 1.0, 0.0], [-1.0, 0.0, 0.0], 1.0),
+];
+
+/// Imports an OBJ through the same path as `Model`, in the file's order.
+fn load_obj(filepath: &str) -> (Vec<Vertex>, Vec<u32>) {
+    let options = LoadOptions {
+        optimize: false,
+        lods: None,
+        mesh_cache: None,
+        ..LoadOptions::default()
+    };
+    let mut meshes = import_obj_meshes(filepath, &options).expect("failed to load OBJ");
+    assert_eq!(meshes.len(), 1);
+    meshes.remove(0)
+}
+
+/// Asserts that every tangent is unit length and orthogonal to its normal,
+/// with a headedness of plus or minus one.
+fn assert_orthonormal(vertices: &[Vertex]) {
+    for vertex in vertices {
+        assert!(
+            (vertex.tangent.magnitude() - 1.0).abs() < TOLERANCE,
+            "tangent {:?} of vertex at {:?} is not unit length",
+            vertex.tangent,
+            vertex.position
+        );
+        assert!(
+            vertex.tangent.dot(vertex.normal).abs() < ORTHOGONALITY_TOLERANCE,
+            "tangent {:?} of vertex at {:?} is not orthogonal to {:?}",
+            vertex.tangent,
+            vertex.position,
+            vertex.normal
+        );
+        assert!(vertex.binormal_headedness.abs() == 1.0);
+    }
+}
+
+/// Asserts that on every triangle the tangent points the way U increases and
+/// the binormal up the texture, the way V decreases since our UVs have their
+/// origin at the top left.
+fn assert_follows_uvs(vertices: &[Vertex], indices: &[u32]) {
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| &vertices[triangle[i] as usize]);
+        let (e1, e2) = (b.position - a.position, c.position - a.position);
+        let (d1, d2) = (b.uv - a.uv, c.uv - a.uv);
+        let determinant = d1.x * d2.y - d2.x * d1.y;
+        if determinant.abs() < TOLERANCE {
+            continue;
+        }
+        let u_direction = (e1 * d2.y - e2 * d1.y) / determinant;
+        let v_direction = (e2 * d1.x - e1 * d2.x) / determinant;
+
+        for vertex in &[a, b, c] {
+            let binormal = vertex.normal.cross(vertex.tangent) * vertex.binormal_headedness;
+            assert!(vertex.tangent.dot(u_direction) > 0.0);
+            assert!(binormal.dot(v_direction) < 0.0);
+        }
+    }
+}
+
+fn assert_close(actual: Vector3<f32>, expected: Vector3<f32>, what: &str) {
+    assert!(
+        (actual - expected).magnitude() < TOLERANCE,
+        "{}: expected {:?}, got {:?}",
+        what,
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn normal_test_matches_reference_tangents() {
+    let (vertices, indices) = load_obj("assets/models/normal_test/normal_test.obj");
+
+    assert_eq!(indices.len(), 36);
+    assert_orthonormal(&vertices);
+    assert_follows_uvs(&vertices, &indices);
+
+    for &index in &indices {
+        let vertex = &vertices[index as usize];
+        let (_, tangent, headedness) = NORMAL_TEST_REFERENCE
+            .iter()
+            .find(|(normal, _, _)| (vertex.normal - Vector3::from(*normal)).magnitude() < TOLERANCE)
+            .unwrap_or_else(|| panic!("unexpected normal {:?}", vertex.normal));
+
+        assert_close(
+            vertex.tangent,
+            Vector3::from(*tangent),
+            &format!("tangent of vertex at {:?}", vertex.position),
+        );
+        assert_eq!(vertex.binormal_headedness, *headedness);
+    }
+}
+
+#[test]
+fn mirrored_uvs_split_vertices() {
+    // Two triangles sharing the edge x = 0, with U mirrored across it like a
+    // symmetric character's UV layout.
+    let vertex = |x: f32, y: f32, u: f32, v: f32| Vertex {
+        position: vec3(x, y, 0.0),
+        normal: Vector3::unit_z(),
+        uv: vec2(u, v),
+        ..Vertex::default()
+    };
+    let vertices = [
+        vertex(0.0, 0.0, 1.0, 1.0),
+        vertex(0.0, 1.0, 1.0, 0.0),
+        vertex(-1.0, 0.0, 0.0, 1.0),
+        vertex(1.0, 0.0, 0.0, 1.0),
+    ];
+    let indices = [0, 1, 2, 0, 3, 1];
+
+    let (vertices, indices) = compute_tangents(&vertices, &indices);
+
+    assert_eq!(vertices.len(), 6);
+    assert_follows_uvs(&vertices, &indices);
+    for (triangle, (tangent, headedness)) in indices
+        .chunks_exact(3)
+        .zip(&[(Vector3::unit_x(), 1.0), (-Vector3::unit_x(), -1.0)])
+    {
+        for &index in triangle {
+            let vertex = &vertices[index as usize];
+            assert_close(vertex.tangent, *tangent, "mirrored tangent");
+            assert_eq!(vertex.binormal_headedness, *headedness);
+        }
+    }
+}
+
+#[test]
+fn degenerate_uvs_get_orthonormal_tangents() {
+    let vertices: Vec<Vertex> = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]
+        .iter()
+        .map(|&[x, y]| Vertex {
+            position: vec3(x, y, 0.0),
+            normal: Vector3::unit_z(),
+            ..Vertex::default()
+        })
+        .collect();
+
+    let (vertices, _) = compute_tangents(&vertices, &[0, 1, 2]);
+
+    assert_orthonormal(&vertices);
+}
+
+#[test]
+fn imported_model_has_orthonormal_tangents() {
+    let (vertices, _) = load_obj("assets/models/3d_other_ufnscjdga/ufnscjdga_LOD0.obj");
+
+    assert_orthonormal(&vertices);
+}
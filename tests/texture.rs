@@ -0,0 +1,25 @@
+use tofu::{Error, Texture};
+
+/// Rejected before any GL call, so no context is needed.
+fn assert_invalid(width: u32, height: u32, channels: u32, pixels: &[u8]) {
+    match Texture::from_pixels(width, height, channels, pixels) {
+        Err(Error::InvalidPixels { .. }) => {}
+        Err(error) => panic!("expected invalid pixels, got {}", error),
+        Ok(_) => panic!("expected invalid pixels"),
+    }
+}
+
+#[test]
+fn mismatched_buffer_lengths_are_an_error() {
+    assert_invalid(2, 2, 4, &[0; 15]);
+    assert_invalid(2, 2, 4, &[0; 17]);
+    assert_invalid(2, 2, 0, &[]);
+    assert_invalid(2, 2, 5, &[0; 20]);
+}
+
+#[test]
+fn oversized_images_are_an_error() {
+    // 65536 * 65536 * 4 wraps to 0 in u32 arithmetic.
+    assert_invalid(65536, 65536, 4, &[]);
+    assert_invalid(u32::MAX, 1, 1, &[]);
+}